@@ -1,4 +1,5 @@
-use syn::{Ident, Type, Visibility, Field, Fields, punctuated::Iter, __private::{quote::{quote, spanned::Spanned}, Span, TokenStream2, ToTokens}, parse::{Parse, Parser}, FieldValue, parse_quote, ItemFn, PathArguments, GenericArgument};
+use syn::{Ident, Type, Visibility, Field, Fields, punctuated::{Iter, Punctuated}, Token, __private::{quote::{quote, spanned::Spanned}, TokenStream2, ToTokens}, parse::{Parse, Parser}, FieldValue, parse_quote, ItemFn, PathArguments, GenericArgument, Expr, LitStr};
+use proc_macro2::TokenTree;
 
 pub struct FieldInfo<'a> {
     name: Option<&'a Ident>,
@@ -6,13 +7,12 @@ pub struct FieldInfo<'a> {
     vis: &'a Visibility,
     attrs: Vec<FieldAttr>,
     is_option: bool,
-    is_vec: bool,
 }
 
 #[derive(Debug)]
 pub struct FieldAttr {
-    _name: String,
-    value: String,
+    name: String,
+    value: LitStr,
 }
 
 impl<'a> FieldInfo<'a> {
@@ -23,37 +23,46 @@ impl<'a> FieldInfo<'a> {
         let vis: &Visibility = &field.vis;
         let attrs = &field.attrs;
 
-        
+
         let is_option = Self::ty_eq(ty, "Option");
-        let is_vec = Self::ty_eq(ty, "Vec");
 
 
         let mut field_attrs = Vec::<FieldAttr>::new();
 
         for attr in attrs {
+            if !attr.path().is_ident("builder") {
+                continue;
+            }
             let meta = &attr.meta;
             let require_list = meta.require_list()?;
-            let args = require_list.parse_args::<syn::ExprAssign>()?;
-            let left_token = args.left.into_token_stream();
-            let left_ident = <syn::Ident as Parse>::parse.parse2(left_token)?;
-            let left = left_ident.to_string();
-
-            if !left.eq("each") {
-                return Err(syn::Error::new(left_ident.__span(), "expected `builder(each = \"...\")`"));
-            }
+            let args_list = require_list.parse_args_with(Punctuated::<syn::ExprAssign, Token![,]>::parse_terminated)?;
+            for args in args_list {
+                let left_token = args.left.into_token_stream();
+                let left_ident = <syn::Ident as Parse>::parse.parse2(left_token)?;
+                let left = left_ident.to_string();
+
+                if !["each", "compute", "alias", "deprecated"].contains(&left.as_str()) {
+                    return Err(syn::Error::new(left_ident.__span(), "expected `builder(each = \"...\")`"));
+                }
 
 
-            let right_token = args.right.into_token_stream();
-            let right_lit = <syn::LitStr as Parse>::parse.parse2(right_token)?;
-            let right = right_lit.value();
+                let right_token = args.right.into_token_stream();
+                let right_lit = <syn::LitStr as Parse>::parse.parse2(right_token)?;
 
-            let field_attr = FieldAttr {
-                _name: left,
-                value: right
-            };
-            field_attrs.push(field_attr);
+                let field_attr = FieldAttr {
+                    name: left,
+                    value: right_lit
+                };
+                field_attrs.push(field_attr);
+            }
         }
 
+        if let Some(attr) = field_attrs.iter().find(|a| a.name.eq("compute")) {
+            if let Some(other) = field_attrs.iter().find(|a| !a.name.eq("compute")) {
+                let msg = format!("`builder(compute = \"...\")` cannot be combined with `{}`", other.name);
+                return Err(syn::Error::new(attr.value.span(), msg));
+            }
+        }
 
 
        Ok(Self {
@@ -62,10 +71,22 @@ impl<'a> FieldInfo<'a> {
         vis,
         attrs: field_attrs,
         is_option,
-        is_vec
        })
     }
 
+    /// The first `#[builder(name = "...")]` attribute with the given name.
+    fn attr(&self, name: &str) -> Option<&FieldAttr> {
+        self.attrs.iter().find(|a| a.name.eq(name))
+    }
+
+    /// The expression of `#[builder(compute = "...")]`, if the field is computed.
+    fn compute(&self) -> Result<Option<Expr>, syn::Error> {
+        match self.attr("compute") {
+            Some(attr) => Ok(Some(attr.value.parse::<Expr>()?)),
+            None => Ok(None),
+        }
+    }
+
     fn ty_eq(ty: &Type, other: &str) -> bool {
         if let Type::Path(path) = ty {
             path.path.segments.first().map(|p|p.ident.eq(other)).unwrap_or(false)
@@ -105,7 +126,7 @@ impl<'a> Iterator for FieldIter<'a> {
     type Item = Result<FieldInfo<'a>, syn::Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|f|FieldInfo::new(f))
+        self.inner.next().map(FieldInfo::new)
     }
 }
 
@@ -117,7 +138,7 @@ pub fn gen_builder_field(field_stream: &FieldStream) -> Result<Vec<Field>, syn::
     for field_info in field_stream.iter() {
         let field_info = field_info?;
         let name = field_info.name;
-        if name.is_none() {
+        if name.is_none() || field_info.compute()?.is_some() {
             continue;
         }
         let name = name.unwrap();
@@ -125,17 +146,15 @@ pub fn gen_builder_field(field_stream: &FieldStream) -> Result<Vec<Field>, syn::
         let vis = field_info.vis;
         let is_option_field = field_info.is_option;
 
-        let builder_field: Field;
-
-        if is_option_field {
-            builder_field = Field::parse_named.parse2(quote! {
+        let builder_field: Field = if is_option_field {
+            Field::parse_named.parse2(quote! {
                #vis #name: #ty
-            })?;
+            })?
         } else {
-            builder_field = Field::parse_named.parse2(quote! {
+            Field::parse_named.parse2(quote! {
                #vis #name: std::option::Option<#ty>
-            })?;
-        }
+            })?
+        };
 
         builder_fields.push(builder_field);
 
@@ -152,7 +171,7 @@ pub fn gen_builder_field_default_value(field_stream: &FieldStream) -> Result<Vec
     for field_info in field_stream.iter(){
         let field_info = field_info?;
         let name = field_info.name;
-        if name.is_none() {
+        if name.is_none() || field_info.compute()?.is_some() {
             continue;
         }
         let name = name.unwrap();
@@ -173,52 +192,55 @@ pub fn gen_field_method(field_stream: &FieldStream) -> Result<Vec<ItemFn>, syn::
     for field_info in field_stream.iter() {
         let field_info = field_info?;
         let name = field_info.name;
-        if name.is_none() {
+        if name.is_none() || field_info.compute()?.is_some() {
             continue;
         }
         let name = name.unwrap();
         let ty = field_info.ty;
         let visi = field_info.vis;
-        
 
-        let is_option = field_info.is_option;
-        let is_vec = field_info.is_vec;
-        let attrs = field_info.attrs;
 
+        let is_option = field_info.is_option;
 
-        if attrs.is_empty() {
+        let mut field_methods: Vec<ItemFn> = Vec::default();
 
-            let fn_item = gen_builder_field_method(name, ty, visi, is_option);
-            if fn_item.is_none() {
-                continue;
-            } 
-            methods.push(fn_item.unwrap());
-        } else {
-            for attr in attrs.iter() {
-                let attr_value = &attr.value;
-                if name.ne(attr_value) {
-                   if let Some(f) = gen_builder_field_method(name, ty, visi, is_option) {
-                    methods.push(f);
-                   }
-                  
-                   let each_name = Ident::new(attr_value.as_str(), Span::call_site());
-                   if let Some(f) = gen_each_field_method(name, &each_name, ty, visi, is_vec) {
-                    methods.push(f);
-                   }
-                 
-
-                } else {
-                    let each_name = Ident::new(attr_value.as_str(), Span::call_site());
-                    if let Some(f) = gen_each_field_method(name, &each_name, ty, visi, is_vec) {
-                        methods.push(f);
+        match field_info.attr("each") {
+            None => {
+                if let Some(f) = gen_builder_field_method(name, ty, visi, is_option) {
+                    field_methods.push(f);
+                }
+            },
+            Some(attr) => {
+                let attr_value = attr.value.value();
+                if name.ne(&attr_value) {
+                    if let Some(f) = gen_builder_field_method(name, ty, visi, is_option) {
+                        field_methods.push(f);
                     }
-                };
-               
+                }
+
+                let each_name = Ident::new(attr_value.as_str(), attr.value.span());
+                field_methods.push(gen_each_field_method(name, &each_name, ty, visi, is_option)?);
             }
+        }
 
+        if let Some(attr) = field_info.attr("deprecated") {
+            let note = &attr.value;
+            for f in field_methods.iter_mut() {
+                f.attrs.push(parse_quote!(#[deprecated(note = #note)]));
+            }
         }
 
-        
+        let mut alias_methods: Vec<ItemFn> = Vec::default();
+        for attr in field_info.attrs.iter().filter(|a| a.name.eq("alias")) {
+            let setter = field_methods.iter().find(|f| f.sig.ident.eq(name));
+            if setter.is_none() {
+                return Err(syn::Error::new(attr.value.span(), format!("`alias` requires a setter named `{}`", name)));
+            }
+            alias_methods.push(gen_alias_method(setter.unwrap(), name, &attr.value)?);
+        }
+
+        methods.append(&mut field_methods);
+        methods.append(&mut alias_methods);
     }
 
     Ok(methods)
@@ -227,10 +249,23 @@ pub fn gen_field_method(field_stream: &FieldStream) -> Result<Vec<ItemFn>, syn::
 pub fn gen_builder_method(target: &Ident, field_stream: &FieldStream) -> Result<ItemFn, syn::Error> {
 
     let mut tokens: Vec<TokenStream2> = Vec::default();
+    let mut computed_tokens: Vec<TokenStream2> = Vec::default();
     let mut field_names: Vec<&Ident> = Vec::default();
 
-    for field_info in field_stream.iter() {
-        let field_info = field_info?;
+    let field_infos = field_stream.iter().collect::<Result<Vec<_>, syn::Error>>()?;
+
+    // computed fields are evaluated in declaration order, so each one may only
+    // refer to plain fields and to computed fields declared before it.
+    let mut pending_computed: Vec<&Ident> = Vec::default();
+    for field_info in field_infos.iter() {
+        if let Some(name) = field_info.name {
+            if field_info.compute()?.is_some() {
+                pending_computed.push(name);
+            }
+        }
+    }
+
+    for field_info in field_infos.iter() {
         let name = field_info.name;
         if name.is_none() {
             continue;
@@ -238,21 +273,28 @@ pub fn gen_builder_method(target: &Ident, field_stream: &FieldStream) -> Result<
         let name: &Ident = name.unwrap();
         field_names.push(name);
 
-        let attrs = field_info.attrs;
         let is_option = field_info.is_option;
 
-        if is_option {
+        if let Some(expr) = field_info.compute()? {
+            if let Some(ident) = find_field_reference(expr.to_token_stream(), &pending_computed, &[]) {
+                let msg = format!("field `{}` is not available when computing `{}`", ident, name);
+                return Err(syn::Error::new(ident.span(), msg));
+            }
+            pending_computed.retain(|n| n.ne(&name));
+
+            let ty = field_info.ty;
+            let token = quote! {
+                let #name: #ty = #expr;
+            };
+            computed_tokens.push(token);
+        } else if is_option {
             let token = quote! {
                 let #name = self.#name.take();
             };
             tokens.push(token);
-        } else if !attrs.is_empty() {
+        } else if field_info.attr("each").is_some() {
             let token = quote! {
-                let #name = if self.#name.is_none() {
-                    std::vec::Vec::default()
-                } else {
-                    self.#name.take().unwrap()
-                };
+                let #name = self.#name.take().unwrap_or_default();
             };
             tokens.push(token);
         } else {
@@ -272,6 +314,7 @@ pub fn gen_builder_method(target: &Ident, field_stream: &FieldStream) -> Result<
     let fn_item: ItemFn = parse_quote! {
         pub fn build(&mut self) -> std::result::Result<#target, std::boxed::Box<dyn std::error::Error>>{
             #(#tokens)*
+            #(#computed_tokens)*
             let target = #target {
                 #(#field_names),*
             };
@@ -283,46 +326,149 @@ pub fn gen_builder_method(target: &Ident, field_stream: &FieldStream) -> Result<
    Ok(fn_item)
 }
 
-fn gen_each_field_method(name: &Ident, each_name: &Ident, ty: &Type, visi: &Visibility, is_vec: bool) -> Option<ItemFn> {
-    if !is_vec {
-        return None;
+/// Finds the first use of one of `names` as a plain identifier in `tokens`.
+///
+/// Macro arguments such as those of `format!` are opaque to the syntax tree,
+/// so the expression is scanned as tokens. Identifiers reached through `.`
+/// (method calls and field access) or through a `::` path are not field uses,
+/// and neither are identifiers followed by a single `:`, which name the fields
+/// of a struct literal. Closure parameters and `let` bindings shadow the field
+/// of the same name for the rest of the enclosing group.
+fn find_field_reference(tokens: TokenStream2, names: &[&Ident], shadowed: &[Ident]) -> Option<Ident> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut shadowed: Vec<Ident> = shadowed.to_vec();
+    // the names of a `let` are bound once its initializer ends.
+    let mut pending_let: Vec<Ident> = Vec::default();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                if let Some(ident) = find_field_reference(group.stream(), names, &shadowed) {
+                    return Some(ident);
+                }
+            },
+            TokenTree::Punct(punct) if punct.as_char() == '|' && starts_closure(&tokens, i) => {
+                let end = tokens[i + 1..].iter().position(|t| is_punct(t, '|')).map_or(tokens.len(), |p| i + 1 + p);
+                shadowed.extend(bound_idents(&tokens[i + 1..end]));
+                i = end;
+            },
+            TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                shadowed.append(&mut pending_let);
+            },
+            TokenTree::Ident(ident) if ident == "let" => {
+                let end = tokens[i + 1..].iter().position(|t| is_punct(t, '=') || is_punct(t, ';')).map_or(tokens.len(), |p| i + 1 + p);
+                pending_let.extend(bound_idents(&tokens[i + 1..end]));
+                i = end;
+                continue;
+            },
+            TokenTree::Ident(ident) => {
+                let after_dot = i > 0 && is_punct(&tokens[i - 1], '.');
+                let after_path = i > 1 && is_punct(&tokens[i - 1], ':') && is_punct(&tokens[i - 2], ':');
+                // a `::` path segment, or a struct literal field name.
+                let before_colon = tokens.get(i + 1).map(|t| is_punct(t, ':')).unwrap_or(false);
+                let is_field = names.iter().any(|n| (*n).eq(ident)) && !shadowed.contains(ident);
+                if !after_dot && !after_path && !before_colon && is_field {
+                    return Some(ident.clone());
+                }
+            },
+            _ => {}
+        }
+        i += 1;
     }
 
-    let inner_ty =  get_inner_type(ty);
+    None
+}
 
-    if inner_ty.is_none() {
-        return None;
+/// Whether the `|` at `index` opens the parameters of a closure rather than
+/// being a bitwise or: it then starts the expression or follows an operator,
+/// a separator or `move`.
+fn starts_closure(tokens: &[TokenTree], index: usize) -> bool {
+    match index.checked_sub(1).map(|i| &tokens[i]) {
+        None => true,
+        Some(TokenTree::Punct(_)) => true,
+        Some(TokenTree::Ident(ident)) => ident == "move",
+        Some(_) => false,
     }
+}
 
-    let inner_ty = inner_ty.unwrap();
+/// The identifiers bound by a pattern, leaving out its type annotations.
+fn bound_idents(tokens: &[TokenTree]) -> Vec<Ident> {
+    let mut idents: Vec<Ident> = Vec::default();
+    let mut in_type = false;
+    for token in tokens {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ':' => in_type = true,
+            TokenTree::Punct(punct) if punct.as_char() == ',' => in_type = false,
+            TokenTree::Group(group) if !in_type => {
+                let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+                idents.extend(bound_idents(&tokens));
+            },
+            TokenTree::Ident(ident) if !in_type && ident != "mut" && ident != "ref" => idents.push(ident.clone()),
+            _ => {}
+        }
+    }
+    idents
+}
 
-    let item_fn: ItemFn = parse_quote! {
-        #visi fn #each_name(&mut self, #each_name: #inner_ty) -> &mut Self {
-            let mut vec = if self.#name.is_none() {
-                std::vec::Vec::default()
-            } else {
-                self.#name.take().unwrap()
-            };
-            vec.push(#each_name);
-            self.#name = std::option::Option::Some(vec);
-            self
+/// Generates a deprecated setter named `alias` that forwards to `setter`, so a
+/// renamed field keeps its old setter working during the transition.
+fn gen_alias_method(setter: &ItemFn, name: &Ident, alias: &LitStr) -> Result<ItemFn, syn::Error> {
+    let alias_name = alias.parse::<Ident>()?;
+    let note = format!("use `{}` instead", name);
+
+    let mut item_fn = setter.clone();
+    item_fn.sig.ident = alias_name;
+    item_fn.attrs.retain(|a| !a.path().is_ident("deprecated"));
+    item_fn.attrs.push(parse_quote!(#[deprecated(note = #note)]));
+    item_fn.attrs.push(parse_quote!(#[allow(deprecated)]));
+    item_fn.block = parse_quote! {{
+        self.#name(#name)
+    }};
+
+    Ok(item_fn)
+}
+
+fn is_punct(token: &TokenTree, ch: char) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.as_char() == ch)
+}
+
+fn gen_each_field_method(name: &Ident, each_name: &Ident, ty: &Type, visi: &Visibility, is_option: bool) -> Result<ItemFn, syn::Error> {
+    let collection_ty = if is_option {
+        get_inner_type(ty).unwrap_or(ty)
+    } else {
+        ty
+    };
+
+    // the builder stores both `T` and `Option<T>` fields as `Option<T>`, so the
+    // collection is created on the first call in either case.
+    let item_fn: ItemFn = if FieldInfo::ty_eq(collection_ty, "Vec") && get_inner_type(collection_ty).is_some() {
+        let inner_ty = get_inner_type(collection_ty).unwrap();
+        parse_quote! {
+            #visi fn #each_name(&mut self, #each_name: #inner_ty) -> &mut Self {
+                self.#name.get_or_insert_with(std::default::Default::default).push(#each_name);
+                self
+            }
+        }
+    } else if FieldInfo::ty_eq(collection_ty, "String") {
+        parse_quote! {
+            #visi fn #each_name(&mut self, #each_name: &str) -> &mut Self {
+                self.#name.get_or_insert_with(std::default::Default::default).push_str(#each_name);
+                self
+            }
         }
+    } else {
+        return Err(syn::Error::new(ty.__span(), "`builder(each = \"...\")` requires a field of type `Vec<T>`, `String`, or an `Option` of either"));
     };
 
-    Some(item_fn)
+    Ok(item_fn)
 }
 
 fn gen_builder_field_method(name: &Ident, ty: &Type, visi: &Visibility, is_option: bool) -> Option<ItemFn> {
     if is_option {
-        let inner_ty =  get_inner_type(ty);
-
-         if inner_ty.is_none() {
-             return None;
-         }
+        let inner_ty = get_inner_type(ty)?;
 
-         let inner_ty = inner_ty.unwrap();
 
-         
          let item_fn: ItemFn = parse_quote! {
              #visi fn #name(&mut self, #name: #inner_ty) -> &mut Self {
                  self.#name = std::option::Option::Some(#name);
@@ -330,7 +476,7 @@ fn gen_builder_field_method(name: &Ident, ty: &Type, visi: &Visibility, is_optio
              }
          };
 
-         return Some(item_fn);
+         Some(item_fn)
 
      } else {
          let item_fn: ItemFn = parse_quote! {
@@ -339,7 +485,7 @@ fn gen_builder_field_method(name: &Ident, ty: &Type, visi: &Visibility, is_optio
                  self
              }
          };
-         return Some(item_fn);
+         Some(item_fn)
 
      }
 }
@@ -348,34 +494,25 @@ fn get_inner_type(ty: &Type) -> Option<&Type> {
 
     match ty {
         Type::Path(path) => {
-            let path_se = path.path.segments.first();
-            if path_se.is_none() {
-                return None;
-            }
-            let path_se = path_se.unwrap();
+            let path_se = path.path.segments.first()?;
             match path_se.arguments {
                 PathArguments::AngleBracketed(ref args) => {
-                    let first_arg = args.args.first();
-                    if first_arg.is_none() {
-                        return None;
-                    }
-
-                    let first_arg = first_arg.unwrap();
+                    let first_arg = args.args.first()?;
                     match first_arg {
                         GenericArgument::Type(arg_type) => {
-                            return Some(arg_type);
+                            Some(arg_type)
                         },
                         _ => {
-                            return None;
+                            None
                         }
                     }
                 },
                 _ => {
-                    return None;
+                    None
                 }
             }
 
         },
-        _ => return None
+        _ => None
     }
-}
\ No newline at end of file
+}
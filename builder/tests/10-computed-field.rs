@@ -0,0 +1,53 @@
+// Some fields are a function of other fields, such as a url assembled from a
+// host and a port. Rather than asking the caller to keep them consistent by
+// hand, #[builder(compute = "...")] gives an expression that build() evaluates
+// once every other field has been resolved.
+//
+// The expression refers to the other fields by name. Computed fields do not get
+// a builder field or a setter, and they are evaluated in declaration order so a
+// computed field may use the computed fields declared before it. Names that
+// only look like later fields, such as the fields of a struct literal or the
+// parameters of a closure, are not uses of them.
+
+use derive_builder::Builder;
+
+pub struct Weight {
+    pub url: usize,
+}
+
+#[derive(Builder)]
+pub struct Endpoint {
+    host: String,
+    port: u16,
+    path: Option<String>,
+    #[builder(compute = "Weight { url: host.len() }.url")]
+    weight: usize,
+    #[builder(compute = "[1u16, 2].iter().map(|url| url * port).sum()")]
+    score: u16,
+    #[builder(compute = "format!(\"{}:{}\", host, port)")]
+    authority: String,
+    #[builder(compute = "format!(\"http://{}{}\", authority, path.as_deref().unwrap_or(\"/\"))")]
+    url: String,
+}
+
+fn main() {
+    let endpoint = Endpoint::builder()
+        .host("localhost".to_owned())
+        .port(8080)
+        .build()
+        .unwrap();
+
+    assert_eq!(endpoint.authority, "localhost:8080");
+    assert_eq!(endpoint.url, "http://localhost:8080/");
+    assert_eq!(endpoint.weight, 9);
+    assert_eq!(endpoint.score, 24240);
+
+    let endpoint = Endpoint::builder()
+        .host("example.com".to_owned())
+        .port(443)
+        .path("/index.html".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(endpoint.url, "http://example.com:443/index.html");
+}
@@ -0,0 +1,17 @@
+// A computed field is evaluated in declaration order, so it cannot depend on a
+// computed field declared after it (or on itself). Report the offending field
+// instead of leaving the caller with an unresolved name in generated code.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Endpoint {
+    host: String,
+    port: u16,
+    #[builder(compute = "format!(\"http://{}\", authority)")]
+    url: String,
+    #[builder(compute = "format!(\"{}:{}\", host, port)")]
+    authority: String,
+}
+
+fn main() {}
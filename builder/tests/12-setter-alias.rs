@@ -0,0 +1,44 @@
+// Renaming a field renames its setter, which breaks every caller at once.
+// #[builder(alias = "old_name")] keeps the old setter around as a deprecated
+// method forwarding to the new one, and #[builder(deprecated = "note")] marks
+// the setters of a field that is on its way out.
+//
+// An alias has the signature of the setter named after the field. For a field
+// with #[builder(each = "...")] that is still the all-at-once setter, unless
+// the one-at-a-time method took over the field's name.
+//
+// Both generate ordinary #[deprecated] attributes, so callers see the usual
+// deprecation warnings and can migrate at their own pace.
+
+#![allow(deprecated)]
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(alias = "program")]
+    executable: String,
+    #[builder(each = "arg", alias = "arguments")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    #[builder(alias = "envs")]
+    env: Vec<String>,
+    #[builder(deprecated = "the working directory is inherited")]
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .program("cargo".to_owned())
+        .arguments(vec!["build".to_owned()])
+        .arg("--release".to_owned())
+        .envs("RUST_LOG=info".to_owned())
+        .current_dir("..".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.env, vec!["RUST_LOG=info"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+}
@@ -0,0 +1,21 @@
+// Calls to an alias or to a deprecated setter produce the regular deprecation
+// lint, pointing the caller at the replacement.
+
+#![deny(deprecated)]
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(alias = "program")]
+    executable: String,
+    #[builder(deprecated = "the working directory is inherited")]
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _ = Command::builder()
+        .program("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+}
@@ -0,0 +1,47 @@
+// #[builder(each = "...")] is not limited to plain Vec fields. On an optional
+// collection the field stays None until the first element is added, and on a
+// String the one-at-a-time method appends a &str with push_str.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Option<Vec<String>>,
+    #[builder(each = "env")]
+    env: Option<Vec<String>>,
+    #[builder(each = "push_title")]
+    title: String,
+    #[builder(each = "push_note")]
+    note: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .arg("--release".to_owned())
+        .push_title("cargo")
+        .push_title(" build")
+        .build()
+        .unwrap();
+
+    assert_eq!(command.args, Some(vec!["build".to_owned(), "--release".to_owned()]));
+    assert_eq!(command.env, None);
+    assert_eq!(command.title, "cargo build");
+    assert_eq!(command.note, None);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env("RUST_LOG=info".to_owned())
+        .push_note("from ")
+        .push_note("ci")
+        .build()
+        .unwrap();
+
+    assert_eq!(command.args, None);
+    assert_eq!(command.env, Some(vec!["RUST_LOG=info".to_owned()]));
+    assert_eq!(command.title, "");
+    assert_eq!(command.note.as_deref(), Some("from ci"));
+}
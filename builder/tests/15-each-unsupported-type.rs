@@ -0,0 +1,14 @@
+// Fields whose type has no one-at-a-time method are reported rather than
+// silently left without one.
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "env")]
+    env: HashMap<String, String>,
+}
+
+fn main() {}
@@ -10,4 +10,10 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-computed-field.rs");
+    t.compile_fail("tests/11-computed-field-unavailable.rs");
+    t.pass("tests/12-setter-alias.rs");
+    t.compile_fail("tests/13-deprecated-setter.rs");
+    t.pass("tests/14-each-option-and-string.rs");
+    t.compile_fail("tests/15-each-unsupported-type.rs");
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use alloc::string::String;
+use core::fmt::{Debug, Write};
+
+/// Lists the fields that differ between two values, for readable test
+/// failures on large structs.
+pub trait DebugDiff {
+    /// One `path: left != right` line for every differing field, with nested
+    /// `derive(CustomDebug)` fields compared field by field and named by their
+    /// dotted path, or `None` if no compared field differs.
+    ///
+    /// Skipped and redacted fields, and fields whose type does not implement
+    /// `PartialEq`, are not compared.
+    fn debug_diff(&self, other: &Self) -> Option<String> {
+        let mut diff = Diff::default();
+        self.diff_into(other, "", &mut diff);
+        diff.finish()
+    }
+
+    #[doc(hidden)]
+    fn diff_into(&self, other: &Self, path: &str, diff: &mut Diff);
+}
+
+/// The differences collected by `DebugDiff::diff_into`.
+#[derive(Default)]
+pub struct Diff {
+    lines: String,
+}
+
+impl Diff {
+    /// The path of the field `name` of the value at `path`.
+    pub fn join(path: &str, name: &str) -> String {
+        let mut joined = String::from(path);
+        if !joined.is_empty() {
+            joined.push('.');
+        }
+        joined.push_str(name);
+        joined
+    }
+
+    pub fn push(&mut self, path: &str, left: &dyn Debug, right: &dyn Debug) {
+        if !self.lines.is_empty() {
+            self.lines.push('\n');
+        }
+        if !path.is_empty() {
+            self.lines.push_str(path);
+            self.lines.push_str(": ");
+        }
+        // writing to a `String` does not fail.
+        let _ = write!(self.lines, "{:?} != {:?}", left, right);
+    }
+
+    fn finish(self) -> Option<String> {
+        if self.lines.is_empty() {
+            None
+        } else {
+            Some(self.lines)
+        }
+    }
+}
+
+/// The two values of a field, compared through whichever of `DiffNested`,
+/// `DiffEq` and `DiffOpaque` applies first when called as
+/// `(&&&DiffProbe(left, right)).diff_field(...)`: method resolution tries the
+/// impl for `&&DiffProbe` first and the one for `DiffProbe` last.
+pub struct DiffProbe<'a, T: ?Sized>(pub &'a T, pub &'a T);
+
+/// Fields deriving `CustomDebug` are compared field by field.
+pub trait DiffNested {
+    fn diff_field(&self, path: &str, diff: &mut Diff, left: &dyn Debug, right: &dyn Debug);
+}
+
+impl<T: ?Sized + DebugDiff> DiffNested for &&DiffProbe<'_, T> {
+    fn diff_field(&self, path: &str, diff: &mut Diff, _: &dyn Debug, _: &dyn Debug) {
+        self.0.diff_into(self.1, path, diff);
+    }
+}
+
+/// Other fields are compared as a whole, and printed as in the Debug output.
+pub trait DiffEq {
+    fn diff_field(&self, path: &str, diff: &mut Diff, left: &dyn Debug, right: &dyn Debug);
+}
+
+impl<T: ?Sized + PartialEq> DiffEq for &DiffProbe<'_, T> {
+    fn diff_field(&self, path: &str, diff: &mut Diff, left: &dyn Debug, right: &dyn Debug) {
+        if self.0 != self.1 {
+            diff.push(path, left, right);
+        }
+    }
+}
+
+/// Fields that can not be compared are left out.
+pub trait DiffOpaque {
+    fn diff_field(&self, path: &str, diff: &mut Diff, left: &dyn Debug, right: &dyn Debug);
+}
+
+impl<T: ?Sized> DiffOpaque for DiffProbe<'_, T> {
+    fn diff_field(&self, _: &str, _: &mut Diff, _: &dyn Debug, _: &dyn Debug) {}
+}
@@ -0,0 +1,47 @@
+//! Runtime support for the `visit` and `diff` features of `derive_debug`,
+//! which additionally implement [`VisitFields`] and `DebugDiff` for every
+//! `derive(CustomDebug)`. `DebugDiff` needs the `diff` feature of this crate
+//! too.
+
+#![no_std]
+
+#[cfg(feature = "diff")]
+extern crate alloc;
+
+#[cfg(feature = "diff")]
+mod diff;
+
+#[cfg(feature = "diff")]
+pub use diff::DebugDiff;
+
+#[cfg(feature = "diff")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::diff::{Diff, DiffEq, DiffNested, DiffOpaque, DiffProbe};
+}
+
+use core::fmt::Debug;
+
+/// Receives the fields of a value one by one, for example to emit them as the
+/// key-value pairs of a log record.
+pub trait FieldVisitor {
+    /// Called once for every field that is not `#[debug(skip)]`, in
+    /// declaration order. Tuple fields are named by their position.
+    fn visit_field(&mut self, name: &str, value: &dyn Debug);
+}
+
+impl<F> FieldVisitor for F
+where
+    F: FnMut(&str, &dyn Debug),
+{
+    fn visit_field(&mut self, name: &str, value: &dyn Debug) {
+        self(name, value)
+    }
+}
+
+/// Hands the fields of a value to a [`FieldVisitor`], as they would appear in
+/// its `Debug` output: redacted, formatted and summarized fields are passed in
+/// that form. For an enum, the fields of the current variant are visited.
+pub trait VisitFields {
+    fn visit_fields(&self, visitor: &mut dyn FieldVisitor);
+}
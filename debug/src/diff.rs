@@ -0,0 +1,132 @@
+use syn::{__private::{TokenStream2, quote::{quote, format_ident}}, DeriveInput, LitStr, Data, Ident, spanned::Spanned};
+
+use crate::handler::{gen_debug_field_value_in, gen_debug_wrappers, gen_self_access, gen_variant_pattern, gen_where_clause, gen_where_predicates, parse_item_attrs, FieldInfo, FieldStream, ENUM_ATTRS, STRUCT_ATTRS, UNION_ATTRS};
+
+/// Generates the `derive_debug_runtime::DebugDiff` impl of an item marked
+/// `#[debug(diff)]`, which the `diff` feature allows. Every field is compared
+/// with `DiffProbe`, which compares fields implementing `DebugDiff` field by
+/// field, other `PartialEq` fields as a whole and leaves out the rest.
+/// Differences are printed like the Debug output.
+///
+/// The impl has the bounds of the Debug impl, plus `PartialEq` bounds inferred
+/// the same way.
+pub fn impl_debug_diff(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let name = &input.ident;
+    let generics = &input.generics;
+
+    let allowed = match &input.data {
+        Data::Struct(_) => STRUCT_ATTRS,
+        Data::Enum(_) => ENUM_ATTRS,
+        Data::Union(_) => UNION_ATTRS,
+    };
+    if !parse_item_attrs(&input.attrs, allowed)?.iter().any(|a| a.name.eq("diff")) {
+        return Ok(TokenStream2::new());
+    }
+
+    let (item_attrs, field_streams, body) = match &input.data {
+        Data::Struct(data) => {
+            let item_attrs = parse_item_attrs(&input.attrs, STRUCT_ATTRS)?;
+            let field_stream = FieldStream::new(&data.fields);
+            let body = gen_diff_fields(&field_stream, gen_self_access, |index, field| {
+                match field.name {
+                    Some(name) => quote!(&__other.#name),
+                    None => {
+                        let index = syn::Index::from(index);
+                        quote!(&__other.#index)
+                    }
+                }
+            })?;
+            (item_attrs, vec![field_stream], body)
+        },
+        Data::Enum(data) => {
+            let item_attrs = parse_item_attrs(&input.attrs, ENUM_ATTRS)?;
+            let field_streams: Vec<FieldStream> = data.variants.iter().map(|v| FieldStream::new(&v.fields)).collect();
+            let mut arms: Vec<TokenStream2> = Vec::default();
+            for (variant, field_stream) in data.variants.iter().zip(field_streams.iter()) {
+                let self_pattern = gen_variant_pattern(&variant.ident, field_stream, "__self")?;
+                let other_pattern = gen_variant_pattern(&variant.ident, field_stream, "__other")?;
+                let body = gen_diff_fields(field_stream, |index, _| {
+                    let binding = format_ident!("__self_{}", index);
+                    quote!(#binding)
+                }, |index, _| {
+                    let binding = format_ident!("__other_{}", index);
+                    quote!(#binding)
+                })?;
+                arms.push(quote! {
+                    #self_pattern => match *__other {
+                        #other_pattern => { #body },
+                        _ => __diff.push(__path, self, __other),
+                    },
+                });
+            }
+            // a different variant is printed as a whole.
+            let body = quote! {
+                #[allow(unreachable_patterns)]
+                match *self {
+                    #(#arms)*
+                }
+            };
+            (item_attrs, field_streams, body)
+        },
+        Data::Union(_) => {
+            // the active members are unknown, so there is nothing to compare.
+            let item_attrs = parse_item_attrs(&input.attrs, UNION_ATTRS)?;
+            (item_attrs, Vec::default(), TokenStream2::new())
+        },
+    };
+
+    let wrappers = gen_debug_wrappers(&field_streams)?;
+
+    // the fields are compared with `PartialEq` when it is implemented, which
+    // for a generic field the impl has to require.
+    let type_params: Vec<&Ident> = generics.type_params().map(|t| &t.ident).collect();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut where_predicates = gen_where_predicates(&type_params, &field_streams, &item_attrs, &quote!(::core::fmt::Debug), FieldInfo::needs_debug)?;
+    where_predicates.extend(gen_where_predicates(&type_params, &field_streams, &item_attrs, &quote!(::core::cmp::PartialEq), FieldInfo::uses_value)?);
+    let gen_where_clause = gen_where_clause(where_clause, where_predicates);
+
+    let token = quote! {
+        impl #impl_generics ::derive_debug_runtime::DebugDiff for #name #ty_generics #gen_where_clause {
+            fn diff_into(&self, __other: &Self, __path: &str, __diff: &mut ::derive_debug_runtime::__private::Diff) {
+                #[allow(unused_imports)]
+                use ::derive_debug_runtime::__private::{DiffEq as _, DiffNested as _, DiffOpaque as _};
+                #wrappers
+                #body
+            }
+        }
+    };
+
+    Ok(token)
+}
+
+/// Generates the comparison of every field of `field_stream` whose value is
+/// printed, `self_access` and `other_access` returning references to the two
+/// values of the field at the given index.
+fn gen_diff_fields(field_stream: &FieldStream, self_access: impl Fn(usize, &FieldInfo) -> TokenStream2, other_access: impl Fn(usize, &FieldInfo) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    let mut calls: Vec<TokenStream2> = Vec::default();
+
+    for (index, field) in field_stream.iter().enumerate() {
+        let field = field?;
+        if !field.uses_value() {
+            continue;
+        }
+        let name_lit_str = match field.name {
+            Some(name) => LitStr::new(name.to_string().as_str(), name.span()),
+            None => LitStr::new(index.to_string().as_str(), field.ty.span()),
+        };
+        let left = self_access(index, &field);
+        let right = other_access(index, &field);
+        let left_value = gen_debug_field_value_in(&field, quote!(self), left.clone())?;
+        let right_value = gen_debug_field_value_in(&field, quote!(__other), right.clone())?;
+        calls.push(quote! {
+            (&&&::derive_debug_runtime::__private::DiffProbe(#left, #right)).diff_field(
+                &::derive_debug_runtime::__private::Diff::join(__path, #name_lit_str),
+                __diff,
+                #left_value,
+                #right_value,
+            );
+        });
+    }
+
+    Ok(quote!(#(#calls)*))
+}
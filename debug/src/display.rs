@@ -0,0 +1,187 @@
+use syn::{__private::{TokenStream2, quote::{quote, format_ident}}, DeriveInput, LitStr, Fields, Ident, Data};
+
+use crate::format::{parse_format_str, FormatPiece};
+use crate::handler::{gen_where_clause, push_inferred_predicates, FieldStream};
+
+pub fn impl_display(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let name = &input.ident;
+    let generics = &input.generics;
+
+    let mut placeholders: Vec<(&syn::Type, TokenStream2)> = Vec::default();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let template = parse_display_attr(&input.attrs, name)?;
+            let field_stream = FieldStream::new(&data.fields);
+            let (format_str, used) = parse_template(&template, &field_stream)?;
+
+            let mut args: Vec<TokenStream2> = Vec::default();
+            for (index, field) in field_stream.iter().enumerate() {
+                let field = field?;
+                let binding = format_ident!("__self_{}", index);
+                let traits: Vec<&TokenStream2> = used.iter().filter(|(i, _)| *i == index).map(|(_, t)| t).collect();
+                if traits.is_empty() {
+                    continue;
+                }
+                let access = match field.name {
+                    Some(name) => quote!(&self.#name),
+                    None => {
+                        let index = syn::Index::from(index);
+                        quote!(&self.#index)
+                    }
+                };
+                args.push(quote!(#binding = #access));
+                for format_trait in traits {
+                    placeholders.push((field.ty, format_trait.clone()));
+                }
+            }
+
+            quote! {
+                ::core::write!(fmt, #format_str, #(#args),*)
+            }
+        },
+        Data::Enum(data) => {
+            let mut arms: Vec<TokenStream2> = Vec::default();
+            for variant in data.variants.iter() {
+                let variant_name = &variant.ident;
+                let template = parse_display_attr(&variant.attrs, variant_name)?;
+                let field_stream = FieldStream::new(&variant.fields);
+                let (format_str, used) = parse_template(&template, &field_stream)?;
+
+                let mut patterns: Vec<TokenStream2> = Vec::default();
+                let mut args: Vec<TokenStream2> = Vec::default();
+                for (index, field) in field_stream.iter().enumerate() {
+                    let field = field?;
+                    let binding = format_ident!("__self_{}", index);
+                    let traits: Vec<&TokenStream2> = used.iter().filter(|(i, _)| *i == index).map(|(_, t)| t).collect();
+                    if traits.is_empty() {
+                        patterns.push(quote!(_));
+                        continue;
+                    }
+                    patterns.push(quote!(ref #binding));
+                    args.push(quote!(#binding = #binding));
+                    for format_trait in traits {
+                        placeholders.push((field.ty, format_trait.clone()));
+                    }
+                }
+
+                let pattern = match &variant.fields {
+                    Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|f| &f.ident);
+                        quote!(Self::#variant_name { #(#names: #patterns),* })
+                    },
+                    Fields::Unnamed(_) => quote!(Self::#variant_name ( #(#patterns),* )),
+                    Fields::Unit => quote!(Self::#variant_name),
+                };
+                arms.push(quote! {
+                    #pattern => ::core::write!(fmt, #format_str, #(#args),*),
+                });
+            }
+
+            quote! {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(name.span(), "`CustomDisplay` supports only structs and enums"));
+        }
+    };
+
+    let type_param_idents: Vec<&Ident> = generics.type_params().map(|t| &t.ident).collect();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut where_predicates: Vec<syn::WherePredicate> = Vec::default();
+    for (ty, format_trait) in placeholders {
+        push_inferred_predicates(&mut where_predicates, &type_param_idents, ty, &format_trait);
+    }
+    let gen_where_clause = gen_where_clause(where_clause, where_predicates);
+
+    let token = quote! {
+        impl #impl_generics ::core::fmt::Display for #name #ty_generics #gen_where_clause {
+            fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    };
+
+    Ok(token)
+}
+
+/// Parses the template of `#[display("...")]`, which is required on structs
+/// and on every enum variant.
+fn parse_display_attr(attrs: &[syn::Attribute], ident: &Ident) -> Result<LitStr, syn::Error> {
+    for attr in attrs {
+        if attr.path().is_ident("display") {
+            return attr.parse_args::<LitStr>();
+        }
+    }
+    Err(syn::Error::new(ident.span(), "missing `#[display(\"...\")]` attribute"))
+}
+
+/// Rewrites the placeholders of `template` to refer to the generated
+/// `__self_<index>` arguments, and returns the field index and formatting
+/// trait of every placeholder.
+///
+/// Placeholders name a field (`{host}`) or, for tuple-like fields, give its
+/// position (`{0}`). The trait follows from the format spec: `{host}` needs
+/// `Display`, `{host:?}` needs `Debug`, `{mask:08b}` needs `Binary` and so on.
+fn parse_template(template: &LitStr, field_stream: &FieldStream) -> Result<(LitStr, Vec<(usize, TokenStream2)>), syn::Error> {
+    let mut field_names: Vec<String> = Vec::default();
+    for field in field_stream.iter() {
+        field_names.push(field?.name.map(|n| n.to_string()).unwrap_or_default());
+    }
+
+    let mut format_str = String::new();
+    let mut used: Vec<(usize, TokenStream2)> = Vec::default();
+
+    for piece in parse_format_str(template)? {
+        let (arg, spec) = match piece {
+            FormatPiece::Text(text) => {
+                format_str.push_str(text.replace('{', "{{").replace('}', "}}").as_str());
+                continue;
+            },
+            FormatPiece::Placeholder { arg, spec } => (arg, spec),
+        };
+
+        let index = if arg.is_empty() {
+            return Err(syn::Error::new(template.span(), "display placeholders must name a field, as in `{field}`"));
+        } else if let Ok(index) = arg.parse::<usize>() {
+            if index >= field_names.len() {
+                return Err(syn::Error::new(template.span(), format!("no field at position {} in display format", index)));
+            }
+            index
+        } else {
+            match field_names.iter().position(|n| n.eq(&arg)) {
+                Some(index) => index,
+                None => return Err(syn::Error::new(template.span(), format!("unknown field `{}` in display format", arg))),
+            }
+        };
+
+        format_str.push_str(format!("{{__self_{}", index).as_str());
+        if let Some(spec) = &spec {
+            format_str.push(':');
+            format_str.push_str(spec);
+        }
+        format_str.push('}');
+        used.push((index, format_trait(spec.as_deref().unwrap_or_default())));
+    }
+
+    Ok((LitStr::new(format_str.as_str(), template.span()), used))
+}
+
+fn format_trait(spec: &str) -> TokenStream2 {
+    if spec.ends_with('?') {
+        return quote!(::core::fmt::Debug);
+    }
+    match spec.chars().last() {
+        Some('x') => quote!(::core::fmt::LowerHex),
+        Some('X') => quote!(::core::fmt::UpperHex),
+        Some('o') => quote!(::core::fmt::Octal),
+        Some('b') => quote!(::core::fmt::Binary),
+        Some('e') => quote!(::core::fmt::LowerExp),
+        Some('E') => quote!(::core::fmt::UpperExp),
+        _ => quote!(::core::fmt::Display),
+    }
+}
@@ -0,0 +1,103 @@
+use syn::LitStr;
+
+/// A piece of a format string: literal text, with `{{` and `}}` already
+/// unescaped, or a `{arg:spec}` placeholder.
+pub enum FormatPiece {
+    Text(String),
+    Placeholder {
+        arg: String,
+        spec: Option<String>,
+    },
+}
+
+/// Splits the format string of `lit` into its pieces, reporting unmatched
+/// braces at the literal.
+pub fn parse_format_str(lit: &LitStr) -> Result<Vec<FormatPiece>, syn::Error> {
+    let value = lit.value();
+    let mut chars = value.chars().peekable();
+    let mut pieces: Vec<FormatPiece> = Vec::default();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            },
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(syn::Error::new(lit.span(), "unmatched `{` in format string")),
+                    }
+                }
+
+                if !text.is_empty() {
+                    pieces.push(FormatPiece::Text(std::mem::take(&mut text)));
+                }
+
+                let (arg, spec) = match placeholder.split_once(':') {
+                    Some((arg, spec)) => (arg.trim(), Some(spec.to_owned())),
+                    None => (placeholder.trim(), None),
+                };
+                pieces.push(FormatPiece::Placeholder { arg: arg.to_owned(), spec });
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            },
+            '}' => {
+                return Err(syn::Error::new(lit.span(), "unmatched `}` in format string"));
+            },
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(FormatPiece::Text(text));
+    }
+
+    Ok(pieces)
+}
+
+/// Checks a field's `#[debug = "..."]` format string, which receives the field
+/// as its only argument: every placeholder must refer to it, either as `{}`
+/// (once) or as `{0}`, and it must be used at least once.
+pub fn check_field_format_str(lit: &LitStr) -> Result<(), syn::Error> {
+    let mut implicit = 0;
+    let mut used = false;
+
+    for piece in parse_format_str(lit)? {
+        let (arg, spec) = match piece {
+            FormatPiece::Text(_) => continue,
+            FormatPiece::Placeholder { arg, spec } => (arg, spec),
+        };
+
+        let index = if arg.is_empty() {
+            implicit += 1;
+            implicit - 1
+        } else if let Ok(index) = arg.parse::<usize>() {
+            index
+        } else {
+            return Err(syn::Error::new(lit.span(), format!("unknown placeholder `{{{}}}`, the field is formatted with `{{}}`", arg)));
+        };
+
+        if index != 0 {
+            return Err(syn::Error::new(lit.span(), format!("format string refers to argument {} but only the field is passed", index)));
+        }
+
+        if spec.map(|s| s.contains('$') || s.contains('*')).unwrap_or(false) {
+            return Err(syn::Error::new(lit.span(), "width and precision arguments are not supported in field format strings"));
+        }
+
+        used = true;
+    }
+
+    if !used {
+        return Err(syn::Error::new(lit.span(), "format string must contain a `{}` placeholder for the field"));
+    }
+
+    Ok(())
+}
@@ -1,37 +1,73 @@
-use syn::{__private::{TokenStream2, quote::quote, ToTokens}, DeriveInput, LitStr, Fields, punctuated::Iter, Field, Ident, Type, parse::{Parse, Parser}, Data, parse_quote};
+use crate::format::check_field_format_str;
+use syn::{__private::{TokenStream2, quote::{quote, format_ident}, ToTokens}, DeriveInput, LitStr, Fields, punctuated::{Iter, Punctuated}, Field, Ident, Type, parse::{Parse, Parser}, Data, parse_quote, visit::{self, Visit}, spanned::Spanned};
 
 pub fn impl_debug(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
     let name  = &input.ident;
     let generics = &input.generics;
-    let struct_attrs: &Vec<syn::Attribute> = &input.attrs;
-    
 
-    let fields = match &input.data {
+    check_attrs(input)?;
+
+    let (item_attrs, field_streams, body) = match &input.data {
         Data::Struct(data) => {
-            &data.fields
+            let item_attrs = parse_item_attrs(&input.attrs, STRUCT_ATTRS)?;
+            let lit_str = item_name_lit(name, &item_attrs);
+            let field_stream = FieldStream::new(&data.fields);
+            let body = if item_attrs.iter().any(|a| a.name.eq("transparent")) {
+                gen_transparent_fmt(name, &field_stream, gen_self_access)?
+            } else {
+                gen_fields_fmt(&lit_str, &field_stream, gen_self_access)?
+            };
+            (item_attrs, vec![field_stream], body)
+        },
+        Data::Enum(data) => {
+            let item_attrs = parse_item_attrs(&input.attrs, ENUM_ATTRS)?;
+            let field_streams: Vec<FieldStream> = data.variants.iter().map(|v| FieldStream::new(&v.fields)).collect();
+            let mut arms: Vec<TokenStream2> = Vec::default();
+            for (variant, field_stream) in data.variants.iter().zip(field_streams.iter()) {
+                arms.push(gen_variant_arm(variant, field_stream)?);
+            }
+            let body = quote! {
+                match *self {
+                    #(#arms)*
+                }
+            };
+            (item_attrs, field_streams, body)
+        },
+        Data::Union(_) => {
+            // the active member is unknown, so none of them can be read.
+            let item_attrs = parse_item_attrs(&input.attrs, UNION_ATTRS)?;
+            let lit_str = item_name_lit(name, &item_attrs);
+            let body = quote! {
+                fmt.debug_struct(#lit_str).finish_non_exhaustive()
+            };
+            (item_attrs, Vec::default(), body)
         },
-        _ => {
-            return Err(syn::Error::new(name.span(), "the field attribute format error"));
-        }
     };
 
-    let field_stream = FieldStream::new(fields);
+    let wrappers = gen_debug_wrappers(&field_streams)?;
 
-    let name_str = name.to_string();
-    let lit_str = LitStr::new(name_str.as_str(), name.span());
+    let type_params: Vec<&Ident> = generics.type_params().map(|t| &t.ident).collect();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let where_predicates = gen_where_predicates(&type_params, &field_streams, &item_attrs, &quote!(::core::fmt::Debug), FieldInfo::needs_debug)?;
+    let gen_where_clause = gen_where_clause(where_clause, where_predicates);
 
-    let exp_lets = gen_debug_field_exp(&field_stream)?;
+    // formatting `self` again through `{:?}` drops the `{:#?}` flag.
+    let compact = if item_attrs.iter().any(|a| a.name.eq("compact")) {
+        quote! {
+            if fmt.alternate() {
+                return ::core::write!(fmt, "{:?}", self);
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
 
-    let params: Vec<&syn::TypeParam> = generics.type_params().collect();
-    let gen_where_clause = gen_where_clause(struct_attrs, params, &field_stream)?;
-    let (_, ty_generics, _) = generics.split_for_impl();
-    
     let token = quote! {
-        impl #ty_generics  std::fmt::Debug for #name #ty_generics #gen_where_clause {
-            fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                let mut debug_struct = fmt.debug_struct(#lit_str);
-                #(#exp_lets;)*
-                debug_struct.finish()
+        impl #impl_generics ::core::fmt::Debug for #name #ty_generics #gen_where_clause {
+            fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #compact
+                #wrappers
+                #body
             }
         }
     };
@@ -39,274 +75,602 @@ pub fn impl_debug(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
    Ok(token)
 }
 
-fn gen_where_clause(struct_attrs: &Vec<syn::Attribute>, type_params: Vec<&syn::TypeParam>, field_stream: &FieldStream) -> Result<syn::WhereClause, syn::Error>  {
+/// Generates a reference to the field of `self` at the given index.
+pub(crate) fn gen_self_access(index: usize, field: &FieldInfo) -> TokenStream2 {
+    match field.name {
+        Some(name) => quote!(&self.#name),
+        None => {
+            let index = syn::Index::from(index);
+            quote!(&self.#index)
+        }
+    }
+}
+
+/// Generates the match arm formatting one enum variant the way std's derive
+/// does, binding each field by reference as `__self_<index>`.
+fn gen_variant_arm(variant: &syn::Variant, field_stream: &FieldStream) -> Result<TokenStream2, syn::Error> {
+    let variant_name = &variant.ident;
+    let lit_str = item_name_lit(variant_name, &parse_item_attrs(&variant.attrs, VARIANT_ATTRS)?);
+
+    let body = gen_fields_fmt(&lit_str, field_stream, |index, _| {
+        let binding = format_ident!("__self_{}", index);
+        quote!(#binding)
+    })?;
+    let pattern = gen_variant_pattern(variant_name, field_stream, "__self")?;
+
+    Ok(quote! {
+        #pattern => #body,
+    })
+}
 
-    let mut where_clause: syn::WhereClause = parse_quote! {
-        where
+/// Generates the pattern matching one enum variant, binding each field whose
+/// value is read by reference as `<prefix>_<index>` and the others with `_`.
+pub(crate) fn gen_variant_pattern(variant_name: &Ident, field_stream: &FieldStream, prefix: &str) -> Result<TokenStream2, syn::Error> {
+    let mut patterns: Vec<TokenStream2> = Vec::default();
+    for (index, field) in field_stream.iter().enumerate() {
+        if field?.uses_value() {
+            let binding = format_ident!("{}_{}", prefix, index);
+            patterns.push(quote!(ref #binding));
+        } else {
+            patterns.push(quote!(_));
+        }
+    }
+
+    let pattern = match field_stream.fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|f| &f.ident);
+            quote!(Self::#variant_name { #(#names: #patterns),* })
+        },
+        Fields::Unnamed(_) => quote!(Self::#variant_name ( #(#patterns),* )),
+        Fields::Unit => quote!(Self::#variant_name),
     };
 
-    let where_predicates = gen_where_predicates(type_params, field_stream, struct_attrs)?;
+    Ok(pattern)
+}
 
-    for where_predicate in where_predicates {
-        where_clause.predicates.push(where_predicate);
+/// Generates the expression formatting a single-field struct exactly like its
+/// field, for `#[debug(transparent)]`.
+fn gen_transparent_fmt(name: &Ident, field_stream: &FieldStream, access: impl Fn(usize, &FieldInfo) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    let fields: Vec<FieldInfo> = field_stream.iter().collect::<Result<_, _>>()?;
+    if fields.len() != 1 || fields[0].is_skipped() {
+        return Err(syn::Error::new(name.span(), "`debug(transparent)` requires a struct with exactly one field"));
     }
 
-    // let where_predicates = gen_where_predicates_for_attr(struct_attrs)?;
+    let value = gen_debug_field_value(&fields[0], access(0, &fields[0]))?;
+    Ok(quote! {
+        ::core::fmt::Debug::fmt(#value, fmt)
+    })
+}
 
-    // for where_predicate in where_predicates {
-    //     where_clause.predicates.push(where_predicate);
-    // } 
+/// Generates the expression formatting `field_stream` under the name
+/// `lit_str`: `debug_struct` for named fields, `debug_tuple` for unnamed
+/// fields and the bare name for unit structs and variants. `access` returns a
+/// reference to the field at the given index.
+fn gen_fields_fmt(lit_str: &LitStr, field_stream: &FieldStream, access: impl Fn(usize, &FieldInfo) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    let mut values: Vec<TokenStream2> = Vec::default();
+    let mut name_lit_strs: Vec<LitStr> = Vec::default();
+    let mut has_skipped = false;
 
-    Ok(where_clause)
+    for (index, field) in field_stream.iter().enumerate() {
+        let field = field?;
+        if field.is_skipped() {
+            has_skipped = true;
+            continue;
+        }
+        values.push(gen_debug_field_value(&field, access(index, &field))?);
+        if let Some(name) = field.name {
+            name_lit_strs.push(LitStr::new(name.to_string().as_str(), name.span()));
+        }
+    }
+
+    // skipped fields are hinted at with `..` like a non-exhaustive struct.
+    let finish = if has_skipped {
+        quote!(finish_non_exhaustive)
+    } else {
+        quote!(finish)
+    };
+
+    let token = match field_stream.fields {
+        Fields::Named(_) => {
+            quote! {
+                fmt.debug_struct(#lit_str)
+                    #(.field(#name_lit_strs, #values))*
+                    .#finish()
+            }
+        },
+        Fields::Unnamed(_) => {
+            quote! {
+                fmt.debug_tuple(#lit_str)
+                    #(.field(#values))*
+                    .#finish()
+            }
+        },
+        Fields::Unit => {
+            quote! {
+                fmt.write_str(#lit_str)
+            }
+        },
+    };
+
+    Ok(token)
 }
 
-fn gen_where_predicates(type_params: Vec<&syn::TypeParam>, field_stream: &FieldStream, struct_attrs: &Vec<syn::Attribute>) -> Result<Vec<syn::WherePredicate>, syn::Error> {
-    
-    let type_param_str: Vec<String> =  type_params.iter().map(|t|t.ident.to_string()).collect();
+/// Adds `error` to the errors collected so far, so that all of them are
+/// reported at once.
+pub(crate) fn combine_error(errors: &mut Option<syn::Error>, error: syn::Error) {
+    match errors {
+        Some(errors) => errors.combine(error),
+        None => *errors = Some(error),
+    }
+}
 
-    let mut type_vec: Vec<String> = Vec::default();
-    let mut vec: Vec<syn::WherePredicate> = Vec::default();
+/// Parses every field and `#[debug(...)]` attribute of the input up front, so
+/// that all of their errors are reported together instead of only the first.
+fn check_attrs(input: &DeriveInput) -> Result<(), syn::Error> {
+    let mut errors: Option<syn::Error> = None;
+
+    let (allowed, fields): (&[&str], Vec<&Field>) = match &input.data {
+        Data::Struct(data) => (STRUCT_ATTRS, data.fields.iter().collect()),
+        Data::Enum(data) => {
+            for variant in data.variants.iter() {
+                if let Err(e) = parse_item_attrs(&variant.attrs, VARIANT_ATTRS) {
+                    combine_error(&mut errors, e);
+                }
+            }
+            (ENUM_ATTRS, data.variants.iter().flat_map(|v| v.fields.iter()).collect())
+        },
+        Data::Union(_) => (UNION_ATTRS, Vec::default()),
+    };
 
+    if let Err(e) = parse_item_attrs(&input.attrs, allowed) {
+        combine_error(&mut errors, e);
+    }
 
-    for type_param in type_params.iter() {
-        let is_empty = type_param.bounds.is_empty();
-        if is_empty {
-          continue;  
+    for field in fields {
+        let field_info = match FieldInfo::new(field) {
+            Ok(field_info) => field_info,
+            Err(e) => {
+                combine_error(&mut errors, e);
+                continue;
+            },
+        };
+        if let Some(tag) = field_info.attr("union_tag").and_then(|a| a.lit_str()) {
+            if let Err(e) = check_union_tag(input, tag) {
+                combine_error(&mut errors, e);
+            }
         }
+    }
 
-        let where_predicate: syn::WherePredicate = parse_quote! {
-            #type_param
-        };
+    match errors {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
 
-        vec.push(where_predicate);
+pub(crate) const STRUCT_ATTRS: &[&str] = &["bound", "compact", "rename", "transparent", "ufmt", "visit", "diff"];
+pub(crate) const ENUM_ATTRS: &[&str] = &["bound", "compact", "ufmt", "visit", "diff"];
+pub(crate) const VARIANT_ATTRS: &[&str] = &["rename"];
+pub(crate) const UNION_ATTRS: &[&str] = &["bound", "rename", "ufmt", "visit", "diff"];
+
+/// Checks that `#[debug(unsafe(union_tag = "..."))]` names another field of a struct
+/// with named fields, which is where the tag is read from.
+fn check_union_tag(input: &DeriveInput, tag: &LitStr) -> Result<(), syn::Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields,
+            _ => return Err(syn::Error::new(tag.span(), "`debug(unsafe(union_tag = \"...\"))` requires a struct with named fields")),
+        },
+        _ => return Err(syn::Error::new(tag.span(), "`debug(unsafe(union_tag = \"...\"))` is only supported on struct fields")),
+    };
+
+    let tag_ident = tag.parse::<Ident>()?;
+    if !fields.named.iter().any(|f| f.ident.as_ref() == Some(&tag_ident)) {
+        return Err(syn::Error::new(tag.span(), format!("no field named `{}` to read the union tag from", tag_ident)));
+    }
+    Ok(())
+}
+
+/// Parses the `#[debug(...)]` attributes of a struct, enum or variant,
+/// accepting only the keys in `allowed`. `compact`, `transparent`, `ufmt`,
+/// `visit` and `diff` are flags, every other key takes a string.
+pub(crate) fn parse_item_attrs(attrs: &[syn::Attribute], allowed: &[&str]) -> Result<Vec<FieldAttr>, syn::Error> {
+    let mut item_attrs: Vec<FieldAttr> = Vec::default();
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+            if !allowed.contains(&key.as_str()) {
+                return Err(meta.error(format!("expected one of: {}", allowed.join(", "))));
+            }
+            if key.eq("ufmt") && !cfg!(feature = "ufmt") {
+                return Err(meta.error("`debug(ufmt)` requires the `ufmt` feature of `derive_debug`"));
+            }
+            if key.eq("visit") && !cfg!(feature = "visit") {
+                return Err(meta.error("`debug(visit)` requires the `visit` feature of `derive_debug`"));
+            }
+            if key.eq("diff") && !cfg!(feature = "diff") {
+                return Err(meta.error("`debug(diff)` requires the `diff` feature of `derive_debug`"));
+            }
+            let value = if key.eq("compact") || key.eq("transparent") || key.eq("ufmt") || key.eq("visit") || key.eq("diff") {
+                None
+            } else {
+                Some(meta.value()?.parse::<LitStr>()?)
+            };
+            item_attrs.push(FieldAttr { name: key, value: value.map(syn::Lit::Str) });
+            Ok(())
+        })?;
+    }
+    Ok(item_attrs)
+}
+
+/// The name printed for an item, honoring `#[debug(rename = "...")]`.
+pub(crate) fn item_name_lit(ident: &Ident, item_attrs: &[FieldAttr]) -> LitStr {
+    match item_attrs.iter().find(|a| a.name.eq("rename")).and_then(|a| a.lit_str().cloned()) {
+        Some(rename) => rename,
+        None => LitStr::new(ident.to_string().as_str(), ident.span()),
     }
+}
+
+/// Extends the input's own where-clause, if any, with `where_predicates`.
+pub(crate) fn gen_where_clause(input_where_clause: Option<&syn::WhereClause>, where_predicates: Vec<syn::WherePredicate>) -> syn::WhereClause {
+
+    let mut where_clause: syn::WhereClause = match input_where_clause {
+        Some(where_clause) => where_clause.clone(),
+        None => parse_quote! {
+            where
+        },
+    };
+
+    for where_predicate in where_predicates {
+        where_clause.predicates.push(where_predicate);
+    }
+
+    where_clause
+}
 
-    let where_predicates_for_attr = gen_where_predicates_for_attr(struct_attrs)?;
+/// The predicates bounding by `bound_trait` the types of the fields that
+/// `needs_bound` selects. `#[debug(bound = "...")]` replaces the inferred
+/// predicates, for every field on the item and for one field on a field.
+pub(crate) fn gen_where_predicates<'a>(type_params: &[&Ident], field_streams: &[FieldStream<'a>], item_attrs: &[FieldAttr], bound_trait: &TokenStream2, needs_bound: fn(&FieldInfo<'a>) -> bool) -> Result<Vec<syn::WherePredicate>, syn::Error> {
 
-    if !where_predicates_for_attr.is_empty() {
-        vec.extend(where_predicates_for_attr);
+    let mut vec: Vec<syn::WherePredicate> = Vec::default();
+
+    // a struct-level bound replaces inference altogether, even when empty.
+    if let Some(where_predicates_for_attr) = gen_where_predicates_for_attr(item_attrs)? {
+        push_where_predicates(&mut vec, where_predicates_for_attr);
         return Ok(vec);
     }
 
-    for field in field_stream.iter() {
+    for field in field_streams.iter().flat_map(|s| s.iter()) {
         let field = field?;
-        let ty = field.ty;
-
-        let where_predicates = gen_where_predicates_for_every_field(&type_param_str, ty);
-        if where_predicates.is_none() {
+        // a field-level bound replaces inference for that field only, and is
+        // kept even on fields formatted without their own `Debug` impl.
+        if let Some(bound) = field.attr("bound").and_then(|a| a.lit_str()) {
+            push_where_predicates(&mut vec, parse_bound(bound)?);
             continue;
         }
-        
-        let where_predicates = where_predicates.unwrap();
-
-        if where_predicates.is_empty() {
+        if !needs_bound(&field) {
             continue;
         }
-
-        for (where_predicate, type_string) in where_predicates {
-            if !type_vec.contains(&type_string) {
-                vec.push(where_predicate);
-                type_vec.push(type_string);
-            }
-        }
+        push_inferred_predicates(&mut vec, type_params, field.ty, bound_trait);
     }
 
-   
-
     Ok(vec)
 }
 
+/// Bounds by `bound_trait` every type that `BoundVisitor` finds in `ty`.
+pub(crate) fn push_inferred_predicates(vec: &mut Vec<syn::WherePredicate>, type_params: &[&Ident], ty: &Type, bound_trait: &TokenStream2) {
+    let mut bound_visitor = BoundVisitor::new(type_params);
+    bound_visitor.visit_type(ty);
 
-
-fn gen_where_predicates_for_attr(struct_attrs: &Vec<syn::Attribute>) -> Result<Vec<syn::WherePredicate>, syn::Error>{
-    let mut where_predicates: Vec<syn::WherePredicate>= Vec::default();
-    for attr in struct_attrs {
-        let meta = &attr.meta;
-        let list = meta.require_list()?;
-        let tokens = &list.tokens;
-        let exp_assign: syn::ExprAssign = parse_quote! {
-            #tokens
-        };
-        
-        let left = &exp_assign.left;
-
-        let left_ident: Ident = parse_quote! {
-            #left
+    let mut inferred: Vec<syn::WherePredicate> = Vec::default();
+    for ty in bound_visitor.bounded_types {
+        let where_predicate: syn::WherePredicate = parse_quote! {
+            #ty: #bound_trait
         };
+        inferred.push(where_predicate);
+    }
+    push_where_predicates(vec, inferred);
+}
 
-        let left_str = left_ident.to_string();
-        if !left_str.eq("bound") {
-            continue;
+fn push_where_predicates(vec: &mut Vec<syn::WherePredicate>, where_predicates: Vec<syn::WherePredicate>) {
+    for where_predicate in where_predicates {
+        if !vec.contains(&where_predicate) {
+            vec.push(where_predicate);
         }
+    }
+}
 
-        let right = &exp_assign.right;
-        let right_str: LitStr = parse_quote! {
-            #right
-        };
-        
-        let token = right_str.parse().unwrap();
-        let where_predicate = syn::WherePredicate::parse.parse2(token)?;
+/// Parses the predicates of a `bound = "..."` string; an empty string means no
+/// predicates at all.
+fn parse_bound(bound: &LitStr) -> Result<Vec<syn::WherePredicate>, syn::Error> {
+    let predicates = bound.parse_with(Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated)?;
+    Ok(predicates.into_iter().collect())
+}
 
-        where_predicates.push(where_predicate);
+/// The predicates of the struct-level `#[debug(bound = "...")]` attributes, or
+/// `None` when there are none.
+fn gen_where_predicates_for_attr(item_attrs: &[FieldAttr]) -> Result<Option<Vec<syn::WherePredicate>>, syn::Error>{
+    let mut where_predicates: Option<Vec<syn::WherePredicate>> = None;
+    for attr in item_attrs.iter().filter(|a| a.name.eq("bound")) {
+        if let Some(bound) = attr.lit_str() {
+            where_predicates.get_or_insert_with(Vec::default).extend(parse_bound(bound)?);
+        }
     }
 
     Ok(where_predicates)
 }
 
 
-fn gen_where_predicates_for_every_field(type_param: &Vec<String>, ty: &Type) -> Option<Vec<(syn::WherePredicate, String)>> {
-    let mut predicates: Vec<(syn::WherePredicate, String)> = Vec::default();
-    match ty {
-        syn::Type::Path(path) => {
-            let path = &path.path;
-            let ident = path.get_ident();
-            
-            if ident.is_none() {
-                let inner_types = get_inner_types(ty);
-                if inner_types.is_none() {
-                    return None;
-                }
-                let inner_types = inner_types.unwrap();
-                if inner_types.is_empty() {
-                    let (is_associated_type, type_str) = is_associated_type(type_param, ty);
-                    if is_associated_type {
-                        let where_predicate: syn::WherePredicate = parse_quote! {
-                            #ty: std::fmt::Debug
-                        };
-                        predicates.push((where_predicate, type_str));
-                        return Some(predicates);
-                    }
-                    return None;
-                }
+/// Collects the types that need a `Debug` bound for a field type to be
+/// `Debug`: type parameters and their associated types, reached through
+/// paths, references, slices, arrays and tuples.
+///
+/// A parameter that only appears inside `PhantomData`, a function pointer or
+/// the parenthesized arguments of an `Fn` trait needs no bound, since none of
+/// those format a value of it.
+pub(crate) struct BoundVisitor<'a> {
+    type_params: &'a [&'a Ident],
+    pub(crate) bounded_types: Vec<Type>,
+}
 
-                
-                let ph_data_field = ty_eq(ty, "PhantomData");
-                if  ph_data_field {
-                    let inner_type = inner_types.first().unwrap();
-                    let inner_type_str = type_to_ident_str(inner_type);
-                    if  inner_type_str.is_none(){
-                        return None;
-                    }
-                    let inner_type_str = inner_type_str.unwrap();
-                    let contain = type_param.contains(&inner_type_str);
-                    if contain {
-                        let where_predicate: syn::WherePredicate = parse_quote! {
-                            std::marker::PhantomData<#inner_type>: std::fmt::Debug
-                        };
-                        predicates.push((where_predicate, inner_type_str));
-                        return Some(predicates);
-                    } else {
-                        return None;
-                    }
-                }
+impl<'a> BoundVisitor<'a> {
+    pub(crate) fn new(type_params: &'a [&'a Ident]) -> Self {
+        Self {
+            type_params,
+            bounded_types: Vec::default(),
+        }
+    }
 
-                for inner_type in inner_types {
-                    let ret = gen_where_predicates_for_every_field(type_param, inner_type);
-                    if ret.is_none() {
-                        continue;
-                    }
-                    let ret = ret.unwrap();
-                    predicates.extend(ret);
+    fn push(&mut self, ty: Type) {
+        if !self.bounded_types.contains(&ty) {
+            self.bounded_types.push(ty);
+        }
+    }
+}
 
-                }
-                return Some(predicates);
-            }
-           
-            let ident = ident.unwrap();
-            let ident_str = ident.to_string();
-            let contain = type_param.contains(&ident_str);
-            if contain {
-                let where_predicate: syn::WherePredicate = parse_quote! {
-                    #ty: std::fmt::Debug
-                };
-                predicates.push((where_predicate, ident_str));
-                return Some(predicates);
-            } else {
-                let (is_associated_type, _) = is_associated_type(type_param, ty);
-                if is_associated_type {
-                    let where_predicate: syn::WherePredicate = parse_quote! {
-                        #ty: std::fmt::Debug
-                    };
-                    predicates.push((where_predicate, ident_str));
-                    return Some(predicates);
-                }
-               return None
+impl<'a, 'ast> Visit<'ast> for BoundVisitor<'a> {
+    fn visit_type_path(&mut self, type_path: &'ast syn::TypePath) {
+        let segments = &type_path.path.segments;
+        let first = segments.first();
+        if type_path.qself.is_none() && first.map(|s| self.type_params.contains(&&s.ident)).unwrap_or(false) {
+            // `T` itself, or an associated type such as `T::Value` or `T::A::B`.
+            self.push(Type::Path(type_path.clone()));
+            return;
+        }
+
+        if type_path.qself.is_some() {
+            // `<T as Trait>::Value` is bounded as a whole whenever it depends
+            // on a type parameter, as `T` itself may not implement `Debug`.
+            let mut inner_visitor = BoundVisitor::new(self.type_params);
+            visit::visit_type_path(&mut inner_visitor, type_path);
+            if !inner_visitor.bounded_types.is_empty() {
+                self.push(Type::Path(type_path.clone()));
             }
+            return;
+        }
 
-        },
-        _ => return None
+        if segments.last().map(|s| s.ident.eq("PhantomData")).unwrap_or(false) {
+            return;
+        }
+
+        visit::visit_type_path(self, type_path);
     }
 
+    fn visit_type_bare_fn(&mut self, _: &'ast syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments(&mut self, _: &'ast syn::ParenthesizedGenericArguments) {}
 }
 
+pub(crate) fn gen_debug_field_value(field: &FieldInfo, access: TokenStream2) -> Result<TokenStream2, syn::Error> {
+    gen_debug_field_value_in(field, quote!(self), access)
+}
 
-fn type_to_ident_str(ty: &Type) -> Option<String> {
-    match ty {
-        syn::Type::Path(path) => {
-            let path = &path.path;
-            let ident = path.get_ident();
-            if ident.is_none() {
-                return None;
-            }
-            let ident = ident.unwrap();
-            let ident_str = ident.to_string();
-            return Some(ident_str);
+/// Like `gen_debug_field_value`, for a field of `owner` rather than of `self`.
+/// Only a tagged union field reads another field of its owner.
+pub(crate) fn gen_debug_field_value_in(field: &FieldInfo, owner: TokenStream2, access: TokenStream2) -> Result<TokenStream2, syn::Error> {
+    if let Some(attr) = field.attr("redact") {
+        let placeholder = match attr.lit_str() {
+            Some(value) => value.value(),
+            None => "<redacted>".to_owned(),
+        };
+        return Ok(quote!(&::core::format_args!("{}", #placeholder)));
+    }
+
+    if let Some(with) = field.with()? {
+        return Ok(quote!(&__DebugWith(#access, #with)));
+    }
+
+    if let Some(tag) = field.attr("union_tag").and_then(|a| a.lit_str()) {
+        return gen_union_field_value(field, tag, owner, access);
+    }
+
+    if field.attr("len_only").is_some() {
+        return Ok(quote!(&__DebugLen(#access)));
+    }
+
+    if let Some(limit) = field.attr("limit").and_then(|a| a.value.as_ref()) {
+        return Ok(quote!(&__DebugLimit(#access, #limit)));
+    }
+
+    match field.attr("debug").and_then(|a| a.lit_str()) {
+        Some(format_str) => {
+            Ok(quote!(&::core::format_args!(#format_str, #access)))
         },
-        _ => None
+        None => Ok(access),
     }
 }
 
-fn gen_debug_field_exp(field_stream: &FieldStream) -> Result<Vec<syn::ExprLet>, syn::Error> {
+/// Formats the member of a union field selected by the struct's tag field as
+/// `Union { member: value }`, or as `Union { .. }` when no variant matches.
+fn gen_union_field_value(field: &FieldInfo, tag: &LitStr, owner: TokenStream2, access: TokenStream2) -> Result<TokenStream2, syn::Error> {
+    let tag = tag.parse::<Ident>()?;
+    let variants = field.union_variants()?;
+    let union_name = field.union_name();
+
+    let arms = variants.iter().map(|variant| {
+        let pat = &variant.pat;
+        let member = &variant.member;
+        let member_lit = LitStr::new(member.to_string().as_str(), member.span());
+        quote! {
+            #pat => fmt.debug_struct(#union_name)
+                .field(#member_lit, unsafe { &(#access).#member })
+                .finish(),
+        }
+    });
+
+    Ok(quote! {
+        &__DebugUnion(|fmt: &mut ::core::fmt::Formatter<'_>| {
+            #[allow(unreachable_patterns)]
+            match #owner.#tag {
+                #(#arms)*
+                _ => fmt.debug_struct(#union_name).finish_non_exhaustive(),
+            }
+        })
+    })
+}
+
+/// One `tag => member` entry of `#[debug(variants(...))]`.
+pub(crate) struct UnionVariant {
+    pub(crate) pat: syn::Pat,
+    pub(crate) member: Ident,
+}
 
-    let mut exp_lets:Vec<syn::ExprLet> = Vec::default();
+impl Parse for UnionVariant {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let pat = syn::Pat::parse_multi(input)?;
+        input.parse::<syn::Token![=>]>()?;
+        let member = input.parse::<Ident>()?;
+        Ok(Self { pat, member })
+    }
+}
 
-    for field in field_stream.iter() {
+/// The wrapper types used by the fields' attributes. They are declared inside
+/// `fmt`, and only when used, so that they never collide with the caller's
+/// items.
+///
+/// `__DebugWith` calls a `#[debug(with = ...)]` function, `__DebugLimit` prints
+/// the first items of a `#[debug(limit = N)]` collection and `__DebugLen` the
+/// length of a `#[debug(len_only)]` one. The collection wrappers only need
+/// `IntoIterator for &T`, plus `Debug` items for `__DebugLimit`.
+/// `__DebugUnion` runs the closure reading the tagged member of a union.
+pub(crate) fn gen_debug_wrappers(field_streams: &[FieldStream]) -> Result<TokenStream2, syn::Error> {
+    let mut uses_with = false;
+    let mut uses_limit = false;
+    let mut uses_len = false;
+    let mut uses_union = false;
+    for field in field_streams.iter().flat_map(|s| s.iter()) {
         let field = field?;
-        let name = field.name;
-        if name.is_none() {
+        if !field.uses_value() {
             continue;
         }
-        let name = name.unwrap();
-        let name_lit_str = LitStr::new(name.to_string().as_str(), name.span());
+        if field.attr("with").is_some() {
+            uses_with = true;
+        } else if field.attr("union_tag").is_some() {
+            uses_union = true;
+        } else if field.attr("len_only").is_some() {
+            uses_len = true;
+        } else if field.attr("limit").is_some() {
+            uses_limit = true;
+        }
+    }
 
-       let attrs = field.attrs;
-       if attrs.is_empty() {
-            let expr_let: syn::ExprLet = parse_quote! {
-                let mut debug_struct = debug_struct.field(#name_lit_str, &self.#name)
-            };
+    let mut wrappers = TokenStream2::new();
 
-            exp_lets.push(expr_let);
-       } else {
-        for attr in attrs.iter() {
-            let attr_name = &attr.name;
+    if uses_with {
+        wrappers.extend(quote! {
+            struct __DebugWith<'__a, __T: ?Sized>(&'__a __T, fn(&__T, &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result);
 
-            if attr_name.eq("debug") {
-                let value = &attr.value;
-                let format_str = LitStr::new(&value, name.span());
-                let expr_let: syn::ExprLet = parse_quote! {
-                    let mut debug_struct = debug_struct.field(#name_lit_str, &std::format_args!(#format_str, &self.#name))
-                };
-                exp_lets.push(expr_let);
+            impl<'__a, __T: ?Sized> ::core::fmt::Debug for __DebugWith<'__a, __T> {
+                fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    (self.1)(self.0, fmt)
+                }
             }
-        }
-       }
-        
+        });
+    }
+
+    if uses_limit {
+        wrappers.extend(quote! {
+            struct __DebugLimit<'__a, __T: ?Sized>(&'__a __T, usize);
+
+            impl<'__a, __T: ?Sized> ::core::fmt::Debug for __DebugLimit<'__a, __T>
+            where
+                &'__a __T: ::core::iter::IntoIterator,
+                <&'__a __T as ::core::iter::IntoIterator>::Item: ::core::fmt::Debug,
+            {
+                fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut list = fmt.debug_list();
+                    let mut iter = ::core::iter::IntoIterator::into_iter(self.0);
+                    for item in ::core::iter::Iterator::take(&mut iter, self.1) {
+                        list.entry(&item);
+                    }
+                    let rest = ::core::iter::Iterator::count(iter);
+                    if rest > 0 {
+                        list.entry(&::core::format_args!("... ({} more)", rest));
+                    }
+                    list.finish()
+                }
+            }
+        });
+    }
+
+    if uses_len {
+        wrappers.extend(quote! {
+            struct __DebugLen<'__a, __T: ?Sized>(&'__a __T);
 
+            impl<'__a, __T: ?Sized> ::core::fmt::Debug for __DebugLen<'__a, __T>
+            where
+                &'__a __T: ::core::iter::IntoIterator,
+            {
+                fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(&::core::iter::Iterator::count(::core::iter::IntoIterator::into_iter(self.0)), fmt)
+                }
+            }
+        });
     }
-    Ok(exp_lets)
+
+    if uses_union {
+        wrappers.extend(quote! {
+            struct __DebugUnion<__F>(__F)
+            where
+                __F: ::core::ops::Fn(&mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result;
+
+            impl<__F> ::core::fmt::Debug for __DebugUnion<__F>
+            where
+                __F: ::core::ops::Fn(&mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result,
+            {
+                fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    (self.0)(fmt)
+                }
+            }
+        });
+    }
+
+    Ok(wrappers)
 }
 
 
 pub struct FieldInfo<'a> {
-    name: Option<&'a Ident>,
-    ty: &'a Type,
+    pub(crate) name: Option<&'a Ident>,
+    pub(crate) ty: &'a Type,
     attrs: Vec<FieldAttr>,
 }
 
 #[derive(Debug)]
 pub struct FieldAttr {
-    name: String,
-    value: String,
+    pub(crate) name: String,
+    pub(crate) value: Option<syn::Lit>,
+}
+
+impl FieldAttr {
+    pub(crate) fn lit_str(&self) -> Option<&LitStr> {
+        match &self.value {
+            Some(syn::Lit::Str(lit_str)) => Some(lit_str),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> FieldInfo<'a> {
@@ -317,53 +681,215 @@ impl<'a> FieldInfo<'a> {
         let attrs = &field.attrs;
 
         let mut field_attrs = Vec::<FieldAttr>::new();
+        let mut errors: Option<syn::Error> = None;
 
         for attr in attrs {
-            let meta = &attr.meta;
-            let meta_and_value = meta.require_name_value()?;
-            let left_path = &meta_and_value.path;
-            let left_ident  = left_path.get_ident();
-            if left_ident.is_none() {
+            if !attr.path().is_ident("debug") {
                 continue;
             }
-            let left_ident = left_ident.unwrap();
-            let left = left_ident.to_string();
-            
-
-            let right_value = &meta_and_value.value;
-            let right_token = right_value.into_token_stream();
-            let right_lit = <syn::LitStr as Parse>::parse.parse2(right_token)?;
-            let right = right_lit.value();
-
-            let field_attr = FieldAttr {
-                name: left,
-                value: right
-            };
-            field_attrs.push(field_attr);
+
+            if let Err(e) = Self::parse_attr(attr, &mut field_attrs) {
+                combine_error(&mut errors, e);
+            }
         }
 
-        
+        let field_info = Self {
+            name,
+            ty,
+            attrs: field_attrs
+        };
 
+        if let Err(e) = field_info.check() {
+            combine_error(&mut errors, e);
+        }
 
-       Ok(Self {
-        name,
-        ty,
-        attrs: field_attrs
-       })
+        match errors {
+            Some(e) => Err(e),
+            None => Ok(field_info),
+        }
     }
 
-}
+    fn parse_attr(attr: &syn::Attribute, field_attrs: &mut Vec<FieldAttr>) -> Result<(), syn::Error> {
+        match &attr.meta {
+            syn::Meta::List(_) => {
+                attr.parse_nested_meta(|meta| {
+                    let left_ident = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+                    match left_ident.as_str() {
+                        "skip" => {
+                            field_attrs.push(FieldAttr { name: left_ident, value: None });
+                            Ok(())
+                        },
+                        "redact" => {
+                            let value = if meta.input.peek(syn::Token![=]) {
+                                Some(meta.value()?.parse::<LitStr>()?)
+                            } else {
+                                None
+                            };
+                            field_attrs.push(FieldAttr { name: left_ident, value: value.map(syn::Lit::Str) });
+                            Ok(())
+                        },
+                        "len_only" => {
+                            field_attrs.push(FieldAttr { name: left_ident, value: None });
+                            Ok(())
+                        },
+                        "limit" => {
+                            let value = meta.value()?.parse::<syn::LitInt>()?;
+                            value.base10_parse::<usize>()?;
+                            field_attrs.push(FieldAttr { name: left_ident, value: Some(syn::Lit::Int(value)) });
+                            Ok(())
+                        },
+                        "bound" => {
+                            let value = meta.value()?.parse::<LitStr>()?;
+                            field_attrs.push(FieldAttr { name: left_ident, value: Some(syn::Lit::Str(value)) });
+                            Ok(())
+                        },
+                        "with" => {
+                            // both `with = "path::to::fn"` and `with = path::to::fn`
+                            let input = meta.value()?;
+                            let value = if input.peek(LitStr) {
+                                input.parse::<LitStr>()?
+                            } else {
+                                let path = input.parse::<syn::Path>()?;
+                                LitStr::new(path.to_token_stream().to_string().as_str(), path.segments[0].ident.span())
+                            };
+                            field_attrs.push(FieldAttr { name: left_ident, value: Some(syn::Lit::Str(value)) });
+                            Ok(())
+                        },
+                        // reading a union member is only sound when the tag
+                        // mapping is right, which the user has to vouch for.
+                        "unsafe" => {
+                            meta.parse_nested_meta(|meta| Self::parse_union_attr(meta, field_attrs))
+                        },
+                        "union_tag" | "variants" => {
+                            Err(meta.error("reading a union member is unsafe: write `debug(unsafe(union_tag = \"...\", variants(...)))`"))
+                        },
+                        _ => Err(meta.error("expected `debug = \"...\"`, `debug(skip)`, `debug(redact)`, `debug(with = ...)`, `debug(limit = N)`, `debug(len_only)`, `debug(bound = \"...\")` or `debug(unsafe(union_tag = \"...\", variants(...)))`")),
+                    }
+                })
+            },
+            syn::Meta::NameValue(meta_and_value) => {
+                let right_value = &meta_and_value.value;
+                let right_token = right_value.into_token_stream();
+                let right_lit = <syn::LitStr as Parse>::parse.parse2(right_token)?;
+
+                let field_attr = FieldAttr {
+                    name: "debug".to_owned(),
+                    value: Some(syn::Lit::Str(right_lit))
+                };
+                field_attrs.push(field_attr);
+                Ok(())
+            },
+            syn::Meta::Path(path) => {
+                Err(syn::Error::new_spanned(path, "expected `debug = \"...\"` or `debug(...)`"))
+            },
+        }
+    }
 
-fn ty_eq(ty: &Type, other: &str) -> bool {
-    if let Type::Path(path) = ty {
-        path.path.segments.first().map(|p|p.ident.eq(other)).unwrap_or(false)
-    } else {
-        false
+    /// Parses the `union_tag = "..."` and `variants(...)` keys of
+    /// `#[debug(unsafe(...))]`.
+    fn parse_union_attr(meta: syn::meta::ParseNestedMeta, field_attrs: &mut Vec<FieldAttr>) -> Result<(), syn::Error> {
+        let left_ident = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+        match left_ident.as_str() {
+            "union_tag" => {
+                let value = meta.value()?.parse::<LitStr>()?;
+                field_attrs.push(FieldAttr { name: left_ident, value: Some(syn::Lit::Str(value)) });
+                Ok(())
+            },
+            "variants" => {
+                // kept as a string like `with`, and parsed again by `union_variants`
+                let content;
+                syn::parenthesized!(content in meta.input);
+                let tokens = content.parse::<TokenStream2>()?;
+                let value = LitStr::new(tokens.to_string().as_str(), meta.path.segments[0].ident.span());
+                field_attrs.push(FieldAttr { name: left_ident, value: Some(syn::Lit::Str(value)) });
+                Punctuated::<UnionVariant, syn::Token![,]>::parse_terminated.parse2(tokens)?;
+                Ok(())
+            },
+            _ => Err(meta.error("expected `union_tag = \"...\"` or `variants(...)`")),
+        }
+    }
+
+    /// Checks the values of the parsed attributes, reporting every problem.
+    fn check(&self) -> Result<(), syn::Error> {
+        let mut errors: Option<syn::Error> = None;
+
+        if let Some(format_str) = self.attr("debug").and_then(|a| a.lit_str()) {
+            if let Err(e) = check_field_format_str(format_str) {
+                combine_error(&mut errors, e);
+            }
+        }
+        if let Err(e) = self.with() {
+            combine_error(&mut errors, e);
+        }
+        if let Some(bound) = self.attr("bound").and_then(|a| a.lit_str()) {
+            if let Err(e) = parse_bound(bound) {
+                combine_error(&mut errors, e);
+            }
+        }
+
+        match (self.attr("union_tag"), self.attr("variants")) {
+            (Some(attr), None) | (None, Some(attr)) => {
+                let span = attr.lit_str().map(|l| l.span()).unwrap_or_else(|| self.ty.span());
+                combine_error(&mut errors, syn::Error::new(span, "`union_tag = \"...\"` and `variants(...)` must be used together in `debug(unsafe(...))`"));
+            },
+            _ => {},
+        }
+
+        match errors {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn attr(&self, name: &str) -> Option<&FieldAttr> {
+        self.attrs.iter().find(|a| a.name.eq(name))
+    }
+
+    /// Whether `#[debug(skip)]` leaves the field out of the output.
+    pub(crate) fn is_skipped(&self) -> bool {
+        self.attr("skip").is_some()
+    }
+
+    /// Whether the output reads the field's value at all.
+    pub(crate) fn uses_value(&self) -> bool {
+        !self.is_skipped() && self.attr("redact").is_none()
+    }
+
+    /// Whether the field's own `Debug` impl is used, and so needs a bound. A
+    /// tagged union field uses the impls of its members, whose types are
+    /// reached through the union's type arguments.
+    pub(crate) fn needs_debug(&self) -> bool {
+        self.uses_value() && self.attr("with").is_none() && self.attr("len_only").is_none()
+    }
+
+    /// The formatter function of `#[debug(with = ...)]`.
+    fn with(&self) -> Result<Option<syn::Path>, syn::Error> {
+        match self.attr("with").and_then(|a| a.lit_str()) {
+            Some(value) => Ok(Some(value.parse::<syn::Path>()?)),
+            None => Ok(None),
+        }
     }
+
+    /// The name a union field is printed under: the last segment of its type.
+    pub(crate) fn union_name(&self) -> String {
+        match self.ty {
+            Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default(),
+            ty => ty.to_token_stream().to_string(),
+        }
+    }
+
+    /// The `tag => member` entries of `#[debug(variants(...))]`.
+    pub(crate) fn union_variants(&self) -> Result<Punctuated<UnionVariant, syn::Token![,]>, syn::Error> {
+        match self.attr("variants").and_then(|a| a.lit_str()) {
+            Some(value) => value.parse_with(Punctuated::<UnionVariant, syn::Token![,]>::parse_terminated),
+            None => Ok(Punctuated::new()),
+        }
+    }
+
 }
 
 pub struct FieldStream<'a> {
-    fields: &'a Fields,
+    pub(crate) fields: &'a Fields,
 }
 
 impl<'a> FieldStream<'a> {
@@ -374,7 +900,7 @@ impl<'a> FieldStream<'a> {
         }
     }
 
-    pub fn iter(&'a self) -> FieldIter<'a> {
+    pub fn iter(&self) -> FieldIter<'a> {
         let iter = self.fields.iter();
         FieldIter {
             inner: iter
@@ -391,66 +917,6 @@ impl<'a> Iterator for FieldIter<'a> {
     type Item = Result<FieldInfo<'a>, syn::Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|f|FieldInfo::new(f))
+        self.inner.next().map(FieldInfo::new)
     }
 }
-
-
-fn get_inner_types(ty: &Type) -> Option<Vec<&Type>> {
-    let mut inner_types: Vec<&Type> = Vec::default();
-    match ty {
-        Type::Path(path) => {
-            let path = &path.path;
-            let segments = &path.segments;
-            if segments.is_empty() {
-                return None;
-            }
-            
-            for segment in segments {
-                let arguments = &segment.arguments;
-                if arguments.is_none() {
-                    continue;
-                }
-                match arguments {
-                    syn::PathArguments::AngleBracketed(gen_args) => {
-                        let gen_args = &gen_args.args;
-                        for gen_arg in gen_args {
-                            match gen_arg {
-                                syn::GenericArgument::Type(gen_type) => {
-                                    inner_types.push(gen_type);
-                                },
-                                _ => {}
-                            }
-                        }
-                    },
-                    _ => {}
-                }
-               
-            }
-            return Some(inner_types);
-
-        },
-        _ => return None
-    }
-}
-
-fn is_associated_type(type_param: &Vec<String>, ty: &Type) -> (bool, String) {
-    
-    match ty {
-        Type::Path(path) => {
-            let path = &path.path;
-            let segments = &path.segments;
-            if segments.is_empty() {
-                return (false, "NONE".to_owned());
-            }
-            let first_segment = segments.first();
-            if first_segment.is_none() {
-                return (false, "NONE".to_owned());
-            }
-            let first_segment = first_segment.unwrap();
-            let ident_str = first_segment.ident.to_string();
-            return (type_param.contains(&ident_str), ident_str);
-        },
-        _ => return (false, "NONE".to_owned())
-    }
-}
\ No newline at end of file
@@ -2,13 +2,51 @@ use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
 
 
+#[cfg(feature = "diff")]
+mod diff;
+mod display;
+mod format;
 mod handler;
+#[cfg(feature = "ufmt")]
+mod udebug;
+#[cfg(feature = "visit")]
+mod visit;
 
 #[proc_macro_derive(CustomDebug, attributes(debug))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let derive_input: DeriveInput = parse_macro_input!(input as DeriveInput);
     let token = handler::impl_debug(&derive_input);
 
+    #[cfg(feature = "ufmt")]
+    let token = token.and_then(|mut token| {
+        token.extend(udebug::impl_udebug(&derive_input)?);
+        Ok(token)
+    });
+
+    #[cfg(feature = "visit")]
+    let token = token.and_then(|mut token| {
+        token.extend(visit::impl_visit_fields(&derive_input)?);
+        Ok(token)
+    });
+
+    #[cfg(feature = "diff")]
+    let token = token.and_then(|mut token| {
+        token.extend(diff::impl_debug_diff(&derive_input)?);
+        Ok(token)
+    });
+
+    if let Err(e) = token {
+        return e.into_compile_error().into();
+    }
+
+    token.unwrap().into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let derive_input: DeriveInput = parse_macro_input!(input as DeriveInput);
+    let token = display::impl_display(&derive_input);
+
     if let Err(e) = token {
         return e.into_compile_error().into();
     }
@@ -0,0 +1,376 @@
+use syn::{__private::{TokenStream2, quote::{quote, format_ident}}, DeriveInput, LitStr, Fields, Ident, Data};
+
+use crate::format::{parse_format_str, FormatPiece};
+use crate::handler::{gen_self_access, gen_variant_pattern, gen_where_clause, item_name_lit, parse_item_attrs, push_inferred_predicates, FieldInfo, FieldStream, ENUM_ATTRS, STRUCT_ATTRS, UNION_ATTRS, VARIANT_ATTRS};
+
+/// Generates the `ufmt::uDebug` impl of an item marked `#[debug(ufmt)]`, which
+/// the `ufmt` feature allows, from the same fields and `#[debug(...)]`
+/// attributes as the `core::fmt::Debug` impl. Other items get no impl.
+///
+/// `ufmt` has no `format_args!`, so `#[debug = "..."]` is limited to the
+/// placeholders `uwrite!` understands, and `#[debug(with = ...)]` fields, whose
+/// formatter takes a `core::fmt::Formatter`, are printed as `..`.
+/// `#[debug(bound = "...")]` names `core::fmt` traits and is not used here: the
+/// bounds are always inferred from the fields.
+pub fn impl_udebug(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let name = &input.ident;
+    let generics = &input.generics;
+    let mut wrappers = UWrappers::default();
+
+    let allowed = match &input.data {
+        Data::Struct(_) => STRUCT_ATTRS,
+        Data::Enum(_) => ENUM_ATTRS,
+        Data::Union(_) => UNION_ATTRS,
+    };
+    if !parse_item_attrs(&input.attrs, allowed)?.iter().any(|a| a.name.eq("ufmt")) {
+        return Ok(TokenStream2::new());
+    }
+
+    let (field_streams, body) = match &input.data {
+        Data::Struct(data) => {
+            let item_attrs = parse_item_attrs(&input.attrs, STRUCT_ATTRS)?;
+            let lit_str = item_name_lit(name, &item_attrs);
+            let field_stream = FieldStream::new(&data.fields);
+            let body = if item_attrs.iter().any(|a| a.name.eq("transparent")) {
+                gen_transparent_ufmt(&field_stream, &mut wrappers, gen_self_access)?
+            } else {
+                gen_fields_ufmt(&lit_str, &field_stream, &mut wrappers, gen_self_access)?
+            };
+            (vec![field_stream], body)
+        },
+        Data::Enum(data) => {
+            let field_streams: Vec<FieldStream> = data.variants.iter().map(|v| FieldStream::new(&v.fields)).collect();
+            let mut arms: Vec<TokenStream2> = Vec::default();
+            for (variant, field_stream) in data.variants.iter().zip(field_streams.iter()) {
+                let variant_name = &variant.ident;
+                let lit_str = item_name_lit(variant_name, &parse_item_attrs(&variant.attrs, VARIANT_ATTRS)?);
+                let pattern = gen_variant_pattern(variant_name, field_stream, "__self")?;
+                let body = gen_fields_ufmt(&lit_str, field_stream, &mut wrappers, |index, _| {
+                    let binding = format_ident!("__self_{}", index);
+                    quote!(#binding)
+                })?;
+                arms.push(quote! {
+                    #pattern => { #body },
+                });
+            }
+            let body = quote! {
+                match *self {
+                    #(#arms)*
+                }
+            };
+            (field_streams, body)
+        },
+        Data::Union(_) => {
+            let item_attrs = parse_item_attrs(&input.attrs, UNION_ATTRS)?;
+            let lit_str = item_name_lit(name, &item_attrs);
+            let opaque = format!("{} {{ .. }}", lit_str.value());
+            (Vec::default(), quote!(f.write_str(#opaque)))
+        },
+    };
+
+    let type_param_idents: Vec<&Ident> = generics.type_params().map(|t| &t.ident).collect();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut where_predicates: Vec<syn::WherePredicate> = Vec::default();
+    for field in field_streams.iter().flat_map(|s| s.iter()) {
+        let field = field?;
+        if !field.needs_debug() {
+            continue;
+        }
+        let ufmt_traits = match field.attr("debug").and_then(|a| a.lit_str()) {
+            Some(format_str) => parse_ufmt_format_str(format_str)?.1,
+            None => vec![quote!(::ufmt::uDebug)],
+        };
+        for ufmt_trait in ufmt_traits.iter() {
+            push_inferred_predicates(&mut where_predicates, &type_param_idents, field.ty, ufmt_trait);
+        }
+    }
+    let gen_where_clause = gen_where_clause(where_clause, where_predicates);
+
+    let wrappers = wrappers.gen()?;
+
+    let token = quote! {
+        impl #impl_generics ::ufmt::uDebug for #name #ty_generics #gen_where_clause {
+            fn fmt<__W>(&self, f: &mut ::ufmt::Formatter<'_, __W>) -> ::core::result::Result<(), __W::Error>
+            where
+                __W: ::ufmt::uWrite + ?::core::marker::Sized,
+            {
+                #wrappers
+                #body
+            }
+        }
+    };
+
+    Ok(token)
+}
+
+/// Generates the statements formatting `field_stream` under the name
+/// `lit_str` with `ufmt`'s `debug_struct` and `debug_tuple` helpers, which
+/// have no `finish_non_exhaustive`: skipped fields are simply left out.
+fn gen_fields_ufmt(lit_str: &LitStr, field_stream: &FieldStream, wrappers: &mut UWrappers, access: impl Fn(usize, &FieldInfo) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    let mut stmts: Vec<TokenStream2> = Vec::default();
+
+    for (index, field) in field_stream.iter().enumerate() {
+        let field = field?;
+        if field.is_skipped() {
+            continue;
+        }
+        let stmt = match field.name {
+            Some(name) => {
+                let name_lit_str = LitStr::new(name.to_string().as_str(), name.span());
+                gen_ufmt_field(&field, access(index, &field), wrappers, |value| quote!(__builder.field(#name_lit_str, #value)?))?
+            },
+            None => gen_ufmt_field(&field, access(index, &field), wrappers, |value| quote!(__builder.field(#value)?))?,
+        };
+        stmts.push(stmt);
+    }
+
+    let token = match field_stream.fields {
+        Fields::Named(_) => {
+            quote! {
+                let mut __builder = f.debug_struct(#lit_str)?;
+                #(#stmts;)*
+                __builder.finish()
+            }
+        },
+        Fields::Unnamed(_) => {
+            quote! {
+                let mut __builder = f.debug_tuple(#lit_str)?;
+                #(#stmts;)*
+                __builder.finish()
+            }
+        },
+        Fields::Unit => {
+            quote! {
+                f.write_str(#lit_str)
+            }
+        },
+    };
+
+    Ok(token)
+}
+
+/// Generates the expression formatting a single-field struct exactly like its
+/// field, for `#[debug(transparent)]`.
+fn gen_transparent_ufmt(field_stream: &FieldStream, wrappers: &mut UWrappers, access: impl Fn(usize, &FieldInfo) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    // the `core::fmt::Debug` impl has already checked there is exactly one field.
+    let field = match field_stream.iter().next() {
+        Some(field) => field?,
+        None => return Ok(TokenStream2::new()),
+    };
+    let access = access(0, &field);
+    gen_ufmt_field(&field, access, wrappers, |value| quote!(::ufmt::uDebug::fmt(#value, f)))
+}
+
+/// Generates the expression passing the `uDebug` value of `field` to `emit`.
+///
+/// A `#[debug(unsafe(union_tag = "..."))]` field has a value of a different type for
+/// every member, so it becomes a `match` on the tag calling `emit` in each arm.
+fn gen_ufmt_field(field: &FieldInfo, access: TokenStream2, wrappers: &mut UWrappers, emit: impl Fn(TokenStream2) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    if let Some(tag) = field.attr("union_tag").and_then(|a| a.lit_str()) {
+        let tag = tag.parse::<Ident>()?;
+        let union_name = field.union_name();
+        let opaque = format!("{} {{ .. }}", union_name);
+        wrappers.uses_str = true;
+        wrappers.uses_member = true;
+
+        let arms = field.union_variants()?.into_iter().map(|variant| {
+            let pat = variant.pat;
+            let member = variant.member;
+            let member_lit = LitStr::new(member.to_string().as_str(), member.span());
+            let value = emit(quote!(&__UDebugMember(#union_name, #member_lit, unsafe { &(#access).#member })));
+            quote!(#pat => #value,)
+        });
+        let opaque = emit(quote!(&__UDebugStr(#opaque)));
+
+        return Ok(quote! {
+            #[allow(unreachable_patterns)]
+            match self.#tag {
+                #(#arms)*
+                _ => #opaque,
+            }
+        });
+    }
+
+    Ok(emit(gen_ufmt_field_value(field, access, wrappers)?))
+}
+
+fn gen_ufmt_field_value(field: &FieldInfo, access: TokenStream2, wrappers: &mut UWrappers) -> Result<TokenStream2, syn::Error> {
+    if let Some(attr) = field.attr("redact") {
+        let placeholder = match attr.lit_str() {
+            Some(value) => value.value(),
+            None => "<redacted>".to_owned(),
+        };
+        wrappers.uses_str = true;
+        return Ok(quote!(&__UDebugStr(#placeholder)));
+    }
+
+    if field.attr("with").is_some() {
+        wrappers.uses_str = true;
+        return Ok(quote!(&__UDebugStr("..")));
+    }
+
+    if field.attr("len_only").is_some() {
+        return Ok(quote!(&::core::iter::Iterator::count(::core::iter::IntoIterator::into_iter(#access))));
+    }
+
+    if let Some(limit) = field.attr("limit").and_then(|a| a.value.as_ref()) {
+        wrappers.uses_limit = true;
+        return Ok(quote!(&__UDebugLimit(#access, #limit)));
+    }
+
+    match field.attr("debug").and_then(|a| a.lit_str()) {
+        Some(format_str) => {
+            let wrapper = format_ident!("__UDebugFmt{}", wrappers.format_strs.len());
+            wrappers.format_strs.push(format_str.clone());
+            Ok(quote!(&#wrapper(#access)))
+        },
+        None => Ok(access),
+    }
+}
+
+/// Rewrites a field's `#[debug = "..."]` format string for `uwrite!`, which
+/// has no positional arguments, and returns it with the `ufmt` trait of every
+/// placeholder, each of which is passed the field.
+fn parse_ufmt_format_str(lit: &LitStr) -> Result<(LitStr, Vec<TokenStream2>), syn::Error> {
+    let mut format_str = String::new();
+    let mut ufmt_traits: Vec<TokenStream2> = Vec::default();
+
+    for piece in parse_format_str(lit)? {
+        let spec = match piece {
+            FormatPiece::Text(text) => {
+                format_str.push_str(text.replace('{', "{{").replace('}', "}}").as_str());
+                continue;
+            },
+            FormatPiece::Placeholder { spec, .. } => spec.unwrap_or_default(),
+        };
+
+        let ufmt_trait = if spec.is_empty() {
+            quote!(::ufmt::uDisplay)
+        } else if spec == "?" || spec == "#?" {
+            quote!(::ufmt::uDebug)
+        } else if spec.ends_with('x') || spec.ends_with('X') {
+            quote!(::ufmt::uDisplayHex)
+        } else {
+            return Err(syn::Error::new(lit.span(), format!("`{{:{}}}` is not supported by `ufmt`", spec)));
+        };
+
+        format_str.push_str(format!("{{:{}}}", spec).as_str());
+        ufmt_traits.push(ufmt_trait);
+    }
+
+    Ok((LitStr::new(format_str.as_str(), lit.span()), ufmt_traits))
+}
+
+/// The wrapper types used by the `uDebug` impl, declared inside `fmt` like the
+/// ones of the `core::fmt::Debug` impl.
+///
+/// `__UDebugStr` writes a string as is, `__UDebugMember` prints the active
+/// member of a tagged union, `__UDebugLimit` prints the first items of a
+/// `#[debug(limit = N)]` collection and `__UDebugFmt<N>` applies the format
+/// string of the N-th formatted field.
+#[derive(Default)]
+struct UWrappers {
+    uses_str: bool,
+    uses_member: bool,
+    uses_limit: bool,
+    format_strs: Vec<LitStr>,
+}
+
+impl UWrappers {
+
+    fn gen(&self) -> Result<TokenStream2, syn::Error> {
+        let mut wrappers = TokenStream2::new();
+
+        if self.uses_str {
+            wrappers.extend(quote! {
+                struct __UDebugStr(&'static str);
+
+                impl ::ufmt::uDebug for __UDebugStr {
+                    fn fmt<__W>(&self, f: &mut ::ufmt::Formatter<'_, __W>) -> ::core::result::Result<(), __W::Error>
+                    where
+                        __W: ::ufmt::uWrite + ?::core::marker::Sized,
+                    {
+                        f.write_str(self.0)
+                    }
+                }
+            });
+        }
+
+        if self.uses_member {
+            wrappers.extend(quote! {
+                struct __UDebugMember<'__a, __T>(&'static str, &'static str, &'__a __T);
+
+                impl<'__a, __T: ::ufmt::uDebug> ::ufmt::uDebug for __UDebugMember<'__a, __T> {
+                    fn fmt<__W>(&self, f: &mut ::ufmt::Formatter<'_, __W>) -> ::core::result::Result<(), __W::Error>
+                    where
+                        __W: ::ufmt::uWrite + ?::core::marker::Sized,
+                    {
+                        f.debug_struct(self.0)?.field(self.1, self.2)?.finish()
+                    }
+                }
+            });
+        }
+
+        if self.uses_limit {
+            wrappers.extend(quote! {
+                struct __UDebugLimit<'__a, __T: ?::core::marker::Sized>(&'__a __T, usize);
+
+                impl<'__a, __T: ?::core::marker::Sized> ::ufmt::uDebug for __UDebugLimit<'__a, __T>
+                where
+                    &'__a __T: ::core::iter::IntoIterator,
+                    <&'__a __T as ::core::iter::IntoIterator>::Item: ::ufmt::uDebug,
+                {
+                    fn fmt<__W>(&self, f: &mut ::ufmt::Formatter<'_, __W>) -> ::core::result::Result<(), __W::Error>
+                    where
+                        __W: ::ufmt::uWrite + ?::core::marker::Sized,
+                    {
+                        struct __More(usize);
+
+                        impl ::ufmt::uDebug for __More {
+                            fn fmt<__W>(&self, f: &mut ::ufmt::Formatter<'_, __W>) -> ::core::result::Result<(), __W::Error>
+                            where
+                                __W: ::ufmt::uWrite + ?::core::marker::Sized,
+                            {
+                                f.write_str("... (")?;
+                                ::ufmt::uDisplay::fmt(&self.0, f)?;
+                                f.write_str(" more)")
+                            }
+                        }
+
+                        let mut list = f.debug_list()?;
+                        let mut iter = ::core::iter::IntoIterator::into_iter(self.0);
+                        for item in ::core::iter::Iterator::take(&mut iter, self.1) {
+                            list.entry(&item)?;
+                        }
+                        let rest = ::core::iter::Iterator::count(iter);
+                        if rest > 0 {
+                            list.entry(&__More(rest))?;
+                        }
+                        list.finish()
+                    }
+                }
+            });
+        }
+
+        for (index, format_str) in self.format_strs.iter().enumerate() {
+            let wrapper = format_ident!("__UDebugFmt{}", index);
+            let (format_str, ufmt_traits) = parse_ufmt_format_str(format_str)?;
+            let args = ufmt_traits.iter().map(|_| quote!(*self.0));
+            wrappers.extend(quote! {
+                struct #wrapper<'__a, __T: ?::core::marker::Sized>(&'__a __T);
+
+                impl<'__a, __T: ?::core::marker::Sized #(+ #ufmt_traits)*> ::ufmt::uDebug for #wrapper<'__a, __T> {
+                    fn fmt<__W>(&self, f: &mut ::ufmt::Formatter<'_, __W>) -> ::core::result::Result<(), __W::Error>
+                    where
+                        __W: ::ufmt::uWrite + ?::core::marker::Sized,
+                    {
+                        ::ufmt::uwrite!(f, #format_str, #(#args),*)
+                    }
+                }
+            });
+        }
+
+        Ok(wrappers)
+    }
+}
@@ -0,0 +1,96 @@
+use syn::{__private::{TokenStream2, quote::{quote, format_ident}}, DeriveInput, LitStr, Data, Ident, spanned::Spanned};
+
+use crate::handler::{gen_debug_field_value, gen_debug_wrappers, gen_self_access, gen_variant_pattern, gen_where_clause, gen_where_predicates, parse_item_attrs, FieldInfo, FieldStream, ENUM_ATTRS, STRUCT_ATTRS, UNION_ATTRS};
+
+/// Generates the `derive_debug_runtime::VisitFields` impl of an item marked
+/// `#[debug(visit)]`, which the `visit` feature allows, passing every field to
+/// the visitor with the same value and bounds as the `core::fmt::Debug` impl.
+pub fn impl_visit_fields(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let name = &input.ident;
+    let generics = &input.generics;
+
+    let allowed = match &input.data {
+        Data::Struct(_) => STRUCT_ATTRS,
+        Data::Enum(_) => ENUM_ATTRS,
+        Data::Union(_) => UNION_ATTRS,
+    };
+    if !parse_item_attrs(&input.attrs, allowed)?.iter().any(|a| a.name.eq("visit")) {
+        return Ok(TokenStream2::new());
+    }
+
+    let (item_attrs, field_streams, body) = match &input.data {
+        Data::Struct(data) => {
+            let item_attrs = parse_item_attrs(&input.attrs, STRUCT_ATTRS)?;
+            let field_stream = FieldStream::new(&data.fields);
+            let body = gen_visit_fields(&field_stream, gen_self_access)?;
+            (item_attrs, vec![field_stream], body)
+        },
+        Data::Enum(data) => {
+            let item_attrs = parse_item_attrs(&input.attrs, ENUM_ATTRS)?;
+            let field_streams: Vec<FieldStream> = data.variants.iter().map(|v| FieldStream::new(&v.fields)).collect();
+            let mut arms: Vec<TokenStream2> = Vec::default();
+            for (variant, field_stream) in data.variants.iter().zip(field_streams.iter()) {
+                let pattern = gen_variant_pattern(&variant.ident, field_stream, "__self")?;
+                let body = gen_visit_fields(field_stream, |index, _| {
+                    let binding = format_ident!("__self_{}", index);
+                    quote!(#binding)
+                })?;
+                arms.push(quote! {
+                    #pattern => { #body },
+                });
+            }
+            let body = quote! {
+                match *self {
+                    #(#arms)*
+                }
+            };
+            (item_attrs, field_streams, body)
+        },
+        Data::Union(_) => {
+            // the active member is unknown, so there is nothing to visit.
+            let item_attrs = parse_item_attrs(&input.attrs, UNION_ATTRS)?;
+            (item_attrs, Vec::default(), TokenStream2::new())
+        },
+    };
+
+    let wrappers = gen_debug_wrappers(&field_streams)?;
+
+    let type_params: Vec<&Ident> = generics.type_params().map(|t| &t.ident).collect();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let where_predicates = gen_where_predicates(&type_params, &field_streams, &item_attrs, &quote!(::core::fmt::Debug), FieldInfo::needs_debug)?;
+    let gen_where_clause = gen_where_clause(where_clause, where_predicates);
+
+    let token = quote! {
+        impl #impl_generics ::derive_debug_runtime::VisitFields for #name #ty_generics #gen_where_clause {
+            fn visit_fields(&self, __visitor: &mut dyn ::derive_debug_runtime::FieldVisitor) {
+                #wrappers
+                #body
+            }
+        }
+    };
+
+    Ok(token)
+}
+
+/// Generates the calls passing every field of `field_stream` that is not
+/// skipped to the visitor, tuple fields being named by their position.
+fn gen_visit_fields(field_stream: &FieldStream, access: impl Fn(usize, &FieldInfo) -> TokenStream2) -> Result<TokenStream2, syn::Error> {
+    let mut calls: Vec<TokenStream2> = Vec::default();
+
+    for (index, field) in field_stream.iter().enumerate() {
+        let field = field?;
+        if field.is_skipped() {
+            continue;
+        }
+        let name_lit_str = match field.name {
+            Some(name) => LitStr::new(name.to_string().as_str(), name.span()),
+            None => LitStr::new(index.to_string().as_str(), field.ty.span()),
+        };
+        let value = gen_debug_field_value(&field, access(index, &field))?;
+        calls.push(quote! {
+            __visitor.visit_field(#name_lit_str, #value);
+        });
+    }
+
+    Ok(quote!(#(#calls)*))
+}
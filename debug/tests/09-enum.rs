@@ -0,0 +1,42 @@
+// Enums format the active variant the same way the standard library's derive
+// does: struct-like variants with debug_struct, tuple-like variants with
+// debug_tuple and unit variants as just their name. Field-level
+// #[debug = "..."] attributes apply inside variants exactly as they do on
+// struct fields.
+//
+// A variant-level #[debug(rename = "...")] replaces the printed variant name.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub enum Message<T> {
+    Quit,
+    Move {
+        x: i32,
+        #[debug = "{}px"]
+        y: i32,
+    },
+    Write(T),
+    #[debug(rename = "Rgb")]
+    ChangeColor(#[debug = "0x{:02x}"] u8, u8, u8),
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn main() {
+    let quit: Message<String> = Message::Quit;
+    assert_eq!(format!("{:?}", quit), "Quit");
+
+    let moved: Message<String> = Message::Move { x: 1, y: 2 };
+    assert_eq!(format!("{:?}", moved), "Move { x: 1, y: 2px }");
+
+    let write = Message::Write("hello");
+    assert_eq!(format!("{:?}", write), r#"Write("hello")"#);
+
+    let color: Message<()> = Message::ChangeColor(255, 0, 128);
+    assert_eq!(format!("{:?}", color), "Rgb(0xff, 0, 128)");
+
+    let expected = "Move {\n    x: 1,\n    y: 2px,\n}";
+    assert_eq!(format!("{:#?}", moved), expected);
+}
@@ -0,0 +1,25 @@
+// Tuple structs print their positional fields with debug_tuple and unit
+// structs print just their name, as with the standard library's derive.
+// Positional fields honor #[debug = "..."] and take part in the bound
+// inference like named fields do.
+
+use derive_debug::CustomDebug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub struct Meters(#[debug = "{:.2}"] f64);
+
+#[derive(CustomDebug)]
+pub struct Pair<T>(T, PhantomData<T>);
+
+#[derive(CustomDebug)]
+pub struct Unit;
+
+fn main() {
+    assert_eq!(format!("{:?}", Meters(1.5)), "Meters(1.50)");
+    assert_eq!(format!("{:#?}", Meters(1.5)), "Meters(\n    1.50,\n)");
+
+    assert_eq!(format!("{:?}", Pair(7, PhantomData)), "Pair(7, PhantomData<i32>)");
+
+    assert_eq!(format!("{:?}", Unit), "Unit");
+}
@@ -0,0 +1,60 @@
+// Some fields must never reach logs. #[debug(skip)] leaves a field out of the
+// output entirely, and the remaining fields are closed with `..` the way
+// finish_non_exhaustive does. #[debug(redact)] keeps the field but prints a
+// placeholder instead of its value, "<redacted>" unless a different one is
+// given with #[debug(redact = "...")].
+//
+// Neither attribute reads the field's value, so the field type does not need
+// to implement Debug and no bound is inferred for it.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub struct Pool;
+
+#[derive(CustomDebug)]
+pub struct Connection<P> {
+    host: &'static str,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "<hidden>")]
+    token: u64,
+    #[debug(skip)]
+    pool: P,
+}
+
+#[derive(CustomDebug)]
+pub enum Credentials {
+    Token(#[debug(redact)] String),
+    Login {
+        user: &'static str,
+        #[debug(skip)]
+        password: String,
+    },
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    let connection = Connection {
+        host: "localhost",
+        password: "hunter2".to_owned(),
+        token: 42,
+        pool: Pool,
+    };
+
+    let debug = format!("{:?}", connection);
+    let expected = r#"Connection { host: "localhost", password: <redacted>, token: <hidden>, .. }"#;
+    assert_eq!(debug, expected);
+
+    let token = Credentials::Token("secret".to_owned());
+    assert_eq!(format!("{:?}", token), "Token(<redacted>)");
+
+    let login = Credentials::Login {
+        user: "admin",
+        password: "secret".to_owned(),
+    };
+    assert_eq!(format!("{:?}", login), r#"Login { user: "admin", .. }"#);
+
+    assert_debug::<Connection<Pool>>();
+}
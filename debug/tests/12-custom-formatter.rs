@@ -0,0 +1,63 @@
+// A format string only goes so far. #[debug(with = "path::to::fmt_fn")] hands
+// the field to a function with the signature
+//
+//     fn(&T, &mut std::fmt::Formatter) -> std::fmt::Result
+//
+// so byte buffers, durations and ids can share formatting helpers. The path may
+// also be written without quotes.
+//
+// The field is formatted only through that function, so no `T: Debug` bound is
+// inferred for it.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+mod hex {
+    use std::fmt;
+
+    pub fn fmt_bytes(bytes: &Vec<u8>, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+fn fmt_id<T>(_: &Id<T>, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("#id")
+}
+
+pub struct Id<T>(PhantomData<T>);
+
+#[derive(CustomDebug)]
+pub struct Packet<T> {
+    #[debug(with = "hex::fmt_bytes")]
+    payload: Vec<u8>,
+    #[debug(with = fmt_id)]
+    id: Id<T>,
+}
+
+#[derive(CustomDebug)]
+pub enum Frame {
+    Data(#[debug(with = "hex::fmt_bytes")] Vec<u8>),
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let packet: Packet<NotDebug> = Packet {
+        payload: vec![0xde, 0xad, 0xbe, 0xef],
+        id: Id(PhantomData),
+    };
+
+    let debug = format!("{:?}", packet);
+    assert_eq!(debug, "Packet { payload: deadbeef, id: #id }");
+
+    let frame = Frame::Data(vec![1, 2]);
+    assert_eq!(format!("{:?}", frame), "Data(0102)");
+
+    assert_debug::<Packet<NotDebug>>();
+}
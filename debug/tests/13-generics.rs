@@ -0,0 +1,57 @@
+// The impl header keeps the input's generic parameters as declared: lifetimes
+// (with their bounds), const generics, `?Sized` parameters and defaults. The
+// input's own where-clause is kept as well and the inferred predicates are
+// appended to it.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct S<'a, const N: usize, T: ?Sized> {
+    name: &'a str,
+    values: [u8; N],
+    value: Box<T>,
+}
+
+#[derive(CustomDebug)]
+pub struct Borrowed<'a, 'b: 'a, T = u8, const N: usize = 2>
+where
+    T: Copy,
+{
+    outer: &'a &'b str,
+    value: Option<T>,
+    values: [u16; N],
+}
+
+#[derive(CustomDebug)]
+pub enum Either<'a, L, R: ?Sized> {
+    Left(Vec<L>),
+    Right(&'a str, Box<R>),
+}
+
+fn assert_debug<F: Debug + ?Sized>() {}
+
+fn main() {
+    let s: S<'_, 3, str> = S {
+        name: "s",
+        values: [1, 2, 3],
+        value: Box::from("unsized"),
+    };
+    let expected = r#"S { name: "s", values: [1, 2, 3], value: "unsized" }"#;
+    assert_eq!(format!("{:?}", s), expected);
+
+    let inner = "b";
+    let borrowed: Borrowed = Borrowed {
+        outer: &inner,
+        value: Some(7),
+        values: [1, 2],
+    };
+    let expected = r#"Borrowed { outer: "b", value: Some(7), values: [1, 2] }"#;
+    assert_eq!(format!("{:?}", borrowed), expected);
+
+    let right: Either<u8, [u8]> = Either::Right("r", Box::from(&[1u8][..]));
+    assert_eq!(format!("{:?}", right), r#"Right("r", [1])"#);
+
+    assert_debug::<S<'static, 0, dyn Debug>>();
+    assert_debug::<Borrowed<'static, 'static, char, 5>>();
+}
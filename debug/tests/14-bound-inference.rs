@@ -0,0 +1,43 @@
+// Bounds are inferred for type parameters wherever a field formats a value of
+// them: behind references, inside slices, arrays and tuples, and as generic
+// arguments of other types. Each distinct type is bounded once.
+//
+// Parameters that only appear in positions that never hold a value, such as
+// PhantomData or the argument and return types of a function pointer, get no
+// bound at all.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub struct Shapes<'a, A, B, C, D: ?Sized, E, F, const N: usize> {
+    reference: &'a A,
+    array: [B; N],
+    tuple: (A, C, Option<&'a B>),
+    slice: &'a D,
+    callback: fn(E) -> F,
+    marker: PhantomData<(E, F)>,
+}
+
+fn assert_debug<T: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let callback: fn(NotDebug) -> NotDebug = |n| n;
+    let shapes: Shapes<u8, char, bool, [i32], NotDebug, NotDebug, 2> = Shapes {
+        reference: &1,
+        array: ['x', 'y'],
+        tuple: (2, true, None),
+        slice: &[3, 4],
+        callback,
+        marker: PhantomData,
+    };
+
+    let debug = format!("{:?}", shapes);
+    assert!(debug.starts_with("Shapes { reference: 1, array: ['x', 'y'], tuple: (2, true, None), slice: [3, 4], callback: "));
+    assert!(debug.contains(", marker: PhantomData<"));
+
+    assert_debug::<Shapes<'static, u8, u8, u8, str, NotDebug, NotDebug, 1>>();
+}
@@ -0,0 +1,80 @@
+// The escape hatch also exists per field: #[debug(bound = "...")] on a field
+// replaces the inferred bounds for that field only, while the other fields keep
+// their inferred bounds. An empty string means the field needs no bound at all,
+// and on the struct an empty bound turns inference off entirely.
+//
+// A field-level bound is also kept on fields that are not formatted through
+// their own Debug impl, such as a `with` field whose formatter needs a bound of
+// its own.
+//
+// Attributes other than #[debug(...)], such as doc comments, are left alone.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+pub struct Opaque<T>(PhantomData<T>);
+
+impl<T> Debug for Opaque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Opaque")
+    }
+}
+
+/// A struct with a doc comment.
+#[derive(CustomDebug)]
+pub struct Field<T: Trait, U, V> {
+    /// Only the associated type is formatted.
+    #[debug(bound = "T::Value: Debug")]
+    values: Vec<T::Value>,
+    value: U,
+    #[debug(bound = "")]
+    opaque: Opaque<V>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "")]
+pub struct Unbounded<T> {
+    opaque: Opaque<T>,
+}
+
+fn show<T: Display>(items: &Vec<T>, f: &mut fmt::Formatter) -> fmt::Result {
+    for item in items {
+        write!(f, "<{}>", item)?;
+    }
+    Ok(())
+}
+
+#[derive(CustomDebug)]
+pub struct Shown<T> {
+    #[debug(with = "show", bound = "T: Display")]
+    items: Vec<T>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    let field: Field<Id, bool, Id> = Field {
+        values: vec![1, 2],
+        value: true,
+        opaque: Opaque(PhantomData),
+    };
+    let expected = "Field { values: [1, 2], value: true, opaque: Opaque }";
+    assert_eq!(format!("{:?}", field), expected);
+
+    let shown = Shown { items: vec![1, 2] };
+    assert_eq!(format!("{:?}", shown), "Shown { items: <1><2> }");
+
+    assert_debug::<Field<Id, bool, Id>>();
+    assert_debug::<Unbounded<Id>>();
+}
@@ -0,0 +1,65 @@
+// Struct-level options control how the whole value is printed.
+//
+// #[debug(compact)] prints on a single line even under {:#?}, which keeps small
+// values readable inside large pretty-printed structures.
+//
+// #[debug(transparent)] prints a newtype exactly like its only field, honoring
+// the field's own attributes.
+//
+// #[debug(rename = "...")] replaces the type name in the output.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Ratio {
+    #[debug = "{:.1}"]
+    value: f64,
+}
+
+#[derive(CustomDebug)]
+#[debug(rename = "Alias")]
+pub struct Original {
+    point: Point,
+    id: UserId,
+}
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub enum Shape {
+    Line { from: Point, to: Point },
+}
+
+fn main() {
+    let point = Point { x: 1, y: 2 };
+    assert_eq!(format!("{:#?}", point), "Point { x: 1, y: 2 }");
+
+    assert_eq!(format!("{:?}", UserId(7)), "7");
+    assert_eq!(format!("{:?}", Ratio { value: 0.25 }), "0.2");
+
+    let original = Original {
+        point: Point { x: 3, y: 4 },
+        id: UserId(9),
+    };
+    assert_eq!(format!("{:?}", original), "Alias { point: Point { x: 3, y: 4 }, id: 9 }");
+    let expected = "Alias {\n    point: Point { x: 3, y: 4 },\n    id: 9,\n}";
+    assert_eq!(format!("{:#?}", original), expected);
+
+    let line = Shape::Line {
+        from: Point { x: 0, y: 0 },
+        to: Point { x: 1, y: 1 },
+    };
+    let expected = "Line { from: Point { x: 0, y: 0 }, to: Point { x: 1, y: 1 } }";
+    assert_eq!(format!("{:#?}", line), expected);
+}
@@ -0,0 +1,9 @@
+// #[debug(transparent)] only makes sense for a struct with a single field.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Pair(u8, u8);
+
+fn main() {}
@@ -0,0 +1,59 @@
+// Many types need a human readable Display next to their Debug output. The
+// CustomDisplay derive takes a template in #[display("...")] on a struct, or on
+// each variant of an enum, whose placeholders name fields: `{host}` for named
+// fields and `{0}` for positional ones.
+//
+// Placeholders keep their format spec, and the bound inferred for a type
+// parameter follows it: `{value}` needs `T: Display`, `{value:?}` needs
+// `T: Debug`, `{mask:08b}` needs `T: Binary` and so on.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Display;
+
+#[derive(CustomDisplay)]
+#[display("{host}:{port}")]
+pub struct Address {
+    host: String,
+    port: u16,
+}
+
+#[derive(CustomDisplay)]
+#[display("{0} ({0:?}) -> {{{1:#x}}}")]
+pub struct Entry<K, V>(K, V);
+
+#[derive(CustomDisplay)]
+pub enum Event<T> {
+    #[display("connected to {0}")]
+    Connected(Address),
+    #[display("received {len} bytes: {payload:?}")]
+    Received { len: usize, payload: T },
+    #[display("closed")]
+    Closed,
+}
+
+fn assert_display<T: Display>() {}
+
+fn main() {
+    let address = Address {
+        host: "localhost".to_owned(),
+        port: 8080,
+    };
+    assert_eq!(address.to_string(), "localhost:8080");
+
+    let entry = Entry("key", 255u8);
+    assert_eq!(entry.to_string(), r#"key ("key") -> {0xff}"#);
+
+    let connected: Event<()> = Event::Connected(address);
+    assert_eq!(connected.to_string(), "connected to localhost:8080");
+
+    let received = Event::Received {
+        len: 2,
+        payload: vec![1u8, 2],
+    };
+    assert_eq!(received.to_string(), "received 2 bytes: [1, 2]");
+
+    assert_eq!(Event::<()>::Closed.to_string(), "closed");
+
+    // Vec<u8> is Debug but not Display, which is all `{payload:?}` needs.
+    assert_display::<Event<Vec<u8>>>();
+}
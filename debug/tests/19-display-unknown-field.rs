@@ -0,0 +1,12 @@
+// Placeholders must refer to fields of the struct or variant.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{host}:{prot}")]
+pub struct Address {
+    host: String,
+    port: u16,
+}
+
+fn main() {}
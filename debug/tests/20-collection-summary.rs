@@ -0,0 +1,42 @@
+// Logging a struct that holds a large collection floods the log.
+// #[debug(limit = N)] prints only the first N items of a field that can be
+// iterated by reference, followed by how many were left out, and
+// #[debug(len_only)] prints only the number of items.
+//
+// Both only need `IntoIterator for &T` on the field type, and len_only does not
+// need the items to implement Debug.
+
+use derive_debug::CustomDebug;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Batch<T, U> {
+    #[debug(limit = 3)]
+    ids: Vec<u32>,
+    #[debug(limit = 2)]
+    names: BTreeMap<&'static str, T>,
+    #[debug(len_only)]
+    payloads: Vec<U>,
+    #[debug(limit = 4)]
+    short: [u8; 2],
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let batch = Batch {
+        ids: (1..=10000).collect(),
+        names: [("a", 1), ("b", 2), ("c", 3)].into_iter().collect(),
+        payloads: vec![NotDebug, NotDebug],
+        short: [7, 8],
+    };
+
+    let debug = format!("{:?}", batch);
+    let expected = r#"Batch { ids: [1, 2, 3, ... (9997 more)], names: [("a", 1), ("b", 2), ... (1 more)], payloads: 2, short: [7, 8] }"#;
+    assert_eq!(debug, expected);
+
+    assert_debug::<Batch<u8, NotDebug>>();
+}
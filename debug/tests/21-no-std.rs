@@ -0,0 +1,63 @@
+// The generated impls refer to everything through ::core, so the derives work
+// in #![no_std] crates where the `std` crate is not in scope.
+//
+// The test still links std from a nested module to get a runtime for main, but
+// the derived types at the crate root cannot see it.
+
+#![no_std]
+
+use core::marker::PhantomData;
+use derive_debug::{CustomDebug, CustomDisplay};
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub struct Register<T> {
+    #[debug = "0x{:04x}"]
+    address: u16,
+    #[debug(limit = 2)]
+    history: [u8; 4],
+    #[debug(len_only)]
+    pending: [u8; 3],
+    #[debug(with = "fmt_marker")]
+    marker: PhantomData<T>,
+    #[debug(redact)]
+    key: u32,
+}
+
+fn fmt_marker<T>(_: &PhantomData<T>, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+    fmt.write_str("marker")
+}
+
+#[derive(CustomDebug, CustomDisplay)]
+pub enum Mode {
+    #[display("read")]
+    Read,
+    #[display("write {0:#x}")]
+    Write(u8),
+}
+
+mod host {
+    extern crate std;
+
+    use std::format;
+
+    pub fn main() {
+        let register: super::Register<()> = super::Register {
+            address: 0x1f,
+            history: [1, 2, 3, 4],
+            pending: [0; 3],
+            marker: super::PhantomData,
+            key: 7,
+        };
+        let expected = "Register { address: 0x001f, history: [1, 2, ... (2 more)], pending: 3, marker: marker, key: <redacted> }";
+        assert_eq!(format!("{:#?}", register), expected);
+
+        assert_eq!(format!("{:?}", super::Mode::Write(16)), "Write(16)");
+        assert_eq!(format!("{}", super::Mode::Write(16)), "write 0x10");
+        assert_eq!(format!("{}", super::Mode::Read), "read");
+    }
+}
+
+fn main() {
+    host::main();
+}
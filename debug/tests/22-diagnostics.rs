@@ -0,0 +1,27 @@
+// Mistakes in the attributes are reported together, each pointing at the
+// attribute that caused it, rather than stopping at the first one found.
+//
+// Format strings are checked at macro expansion time: a field format string
+// has to use its value exactly once, through `{}` or `{0}`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug(unknown)]
+    name: &'static str,
+
+    #[debug = "{} and {}"]
+    bitmask: u8,
+
+    #[debug = "{:08b"]
+    mask: u8,
+
+    #[debug(bound = "T Debug")]
+    value: u32,
+
+    #[debug]
+    other: u32,
+}
+
+fn main() {}
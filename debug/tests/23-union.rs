@@ -0,0 +1,75 @@
+// Unions can not be read safely without knowing which member is active, so by
+// default they derive an opaque Debug impl that prints only the union's name:
+//
+//     RawValue { .. }
+//
+// FFI structs usually keep a tag field next to the union saying which member
+// was written. #[debug(unsafe(union_tag = "...", variants(...)))] on the union
+// field names that tag field and maps each tag value to a member, which is then
+// printed as if the union were a struct with just that field. Tag values that
+// are not listed fall back to the opaque rendering.
+//
+// The members are printed with their own Debug impls, so a union generic over
+// a type parameter gets the same inferred bound as any other generic field.
+//
+// The mapping is trusted, hence the unsafe(...) wrapper: listing the wrong
+// member for a tag reads the union as the wrong type, exactly like the
+// equivalent hand-written unsafe code.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug, Clone, Copy)]
+#[repr(C)]
+pub union RawValue {
+    int: i64,
+    float: f64,
+    flag: bool,
+}
+
+#[derive(CustomDebug)]
+#[debug(rename = "Opaque")]
+pub union Renamed {
+    int: i64,
+}
+
+#[derive(CustomDebug)]
+#[repr(C)]
+pub struct Value {
+    kind: u8,
+    #[debug(unsafe(union_tag = "kind", variants(0 => int, 1 => float, 2 | 3 => flag)))]
+    raw: RawValue,
+}
+
+#[derive(Clone, Copy)]
+pub union Raw<T: Copy> {
+    a: T,
+    b: u32,
+}
+
+#[derive(CustomDebug)]
+pub struct Tagged<T: Copy> {
+    kind: u8,
+    #[debug(unsafe(union_tag = "kind", variants(0 => a, 1 => b)))]
+    raw: Raw<T>,
+}
+
+fn main() {
+    let raw = RawValue { int: 7 };
+    assert_eq!(format!("{:?}", raw), "RawValue { .. }");
+    assert_eq!(format!("{:?}", Renamed { int: 7 }), "Opaque { .. }");
+
+    let int = Value { kind: 0, raw: RawValue { int: -3 } };
+    assert_eq!(format!("{:?}", int), "Value { kind: 0, raw: RawValue { int: -3 } }");
+
+    let float = Value { kind: 1, raw: RawValue { float: 1.5 } };
+    assert_eq!(format!("{:?}", float), "Value { kind: 1, raw: RawValue { float: 1.5 } }");
+
+    let flag = Value { kind: 3, raw: RawValue { flag: true } };
+    assert_eq!(format!("{:?}", flag), "Value { kind: 3, raw: RawValue { flag: true } }");
+
+    let unknown = Value { kind: 9, raw: RawValue { int: 0 } };
+    assert_eq!(format!("{:?}", unknown), "Value { kind: 9, raw: RawValue { .. } }");
+
+    let tagged = Tagged { kind: 0, raw: Raw { a: 'x' } };
+    assert_eq!(format!("{:?}", tagged), "Tagged { kind: 0, raw: Raw { a: 'x' } }");
+}
@@ -0,0 +1,30 @@
+// The tag of #[debug(unsafe(union_tag = "..."))] has to be another field of
+// the same struct, and has to come with the variants(...) mapping. Leaving out
+// the unsafe(...) wrapper is an error, since reading a union member is only
+// sound when the mapping is right.
+
+use derive_debug::CustomDebug;
+
+#[derive(Clone, Copy)]
+pub union RawValue {
+    int: i64,
+    float: f64,
+}
+
+#[derive(CustomDebug)]
+pub struct Value {
+    kind: u8,
+    #[debug(unsafe(union_tag = "knid", variants(0 => int, 1 => float)))]
+    raw: RawValue,
+    #[debug(unsafe(union_tag = "kind"))]
+    other: RawValue,
+}
+
+#[derive(CustomDebug)]
+pub struct Plain {
+    kind: u8,
+    #[debug(union_tag = "kind", variants(0 => int, 1 => float))]
+    raw: RawValue,
+}
+
+fn main() {}
@@ -0,0 +1,109 @@
+// With the `ufmt` feature, #[debug(ufmt)] on a struct, enum or union makes
+// derive(CustomDebug) additionally implement ufmt::uDebug from the same fields
+// and attributes, so that one derive serves both host logging through
+// core::fmt and device logging through ufmt. Items without the attribute only
+// implement Debug, so their fields need not implement uDebug.
+//
+// ufmt has no format_args!, so #[debug = "..."] may only use the placeholders
+// that ufmt::uwrite! understands ({}, {:?}, {:#?} and the hex forms), and
+// fields with #[debug(with = ...)] are printed as `..` because their formatter
+// takes a core::fmt::Formatter.
+//
+// Run with `cargo test --features ufmt`.
+
+use derive_debug::CustomDebug;
+use ufmt::uDebug;
+
+#[derive(CustomDebug)]
+#[debug(ufmt)]
+pub struct Sample<T> {
+    id: u16,
+    #[debug = "0x{:04x}"]
+    flags: u16,
+    #[debug(redact)]
+    key: u32,
+    #[debug(skip)]
+    scratch: u8,
+    #[debug(limit = 2)]
+    values: Vec<T>,
+    #[debug(len_only)]
+    log: Vec<u8>,
+    #[debug(with = fmt_status)]
+    status: u8,
+}
+
+fn fmt_status(status: &u8, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fmt.write_str(if *status == 0 { "ok" } else { "error" })
+}
+
+#[derive(CustomDebug)]
+#[debug(ufmt)]
+pub enum Event {
+    Boot,
+    Reading(i32),
+    #[debug(rename = "Fault")]
+    Error { code: u8 },
+}
+
+#[derive(CustomDebug)]
+#[debug(ufmt)]
+#[debug(transparent)]
+pub struct Id(u32);
+
+#[derive(Clone, Copy)]
+pub union RawValue {
+    int: i32,
+    flag: bool,
+}
+
+#[derive(CustomDebug)]
+#[debug(ufmt)]
+pub struct Value {
+    kind: u8,
+    #[debug(unsafe(union_tag = "kind", variants(0 => int, 1 => flag)))]
+    raw: RawValue,
+}
+
+#[derive(CustomDebug)]
+pub struct HostOnly {
+    elapsed: std::time::Duration,
+}
+
+fn udebug<T: uDebug>(value: &T) -> String {
+    let mut out = String::new();
+    ufmt::uwrite!(out, "{:?}", value).unwrap();
+    out
+}
+
+fn main() {
+    let sample = Sample {
+        id: 7,
+        flags: 0x2a,
+        key: 1234,
+        scratch: 0,
+        values: vec![1i8, 2, 3],
+        log: vec![0; 5],
+        status: 0,
+    };
+    assert_eq!(
+        udebug(&sample),
+        "Sample { id: 7, flags: 0x002a, key: <redacted>, values: [1, 2, ... (1 more)], log: 5, status: .. }",
+    );
+    assert_eq!(
+        format!("{:?}", sample),
+        "Sample { id: 7, flags: 0x002a, key: <redacted>, values: [1, 2, ... (1 more)], log: 5, status: ok, .. }",
+    );
+
+    assert_eq!(udebug(&Event::Boot), "Boot");
+    assert_eq!(udebug(&Event::Reading(-3)), "Reading(-3)");
+    assert_eq!(udebug(&Event::Error { code: 2 }), "Fault { code: 2 }");
+    assert_eq!(udebug(&Id(9)), "9");
+
+    let value = Value { kind: 1, raw: RawValue { flag: true } };
+    assert_eq!(udebug(&value), "Value { kind: 1, raw: RawValue { flag: true } }");
+    let value = Value { kind: 5, raw: RawValue { int: 0 } };
+    assert_eq!(udebug(&value), "Value { kind: 5, raw: RawValue { .. } }");
+
+    let host_only = HostOnly { elapsed: std::time::Duration::from_millis(5) };
+    assert_eq!(format!("{:?}", host_only), "HostOnly { elapsed: 5ms }");
+}
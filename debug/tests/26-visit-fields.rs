@@ -0,0 +1,102 @@
+// Debug output is a single string, which loses the structure of the value.
+// With the `visit` feature, #[debug(visit)] makes derive(CustomDebug)
+// additionally implement derive_debug_runtime::VisitFields, whose visit_fields
+// method hands every field to a FieldVisitor by name, for example to build the
+// key-value pairs of a log record. Items without the attribute only get Debug,
+// so turning the feature on from another crate changes nothing here.
+//
+// The visitor sees each field as the Debug output would show it: skipped
+// fields are left out, while redacted, formatted and summarized fields are
+// passed in that form. Tuple fields are named by their position, and for an
+// enum the fields of the current variant are visited.
+//
+// Run with `cargo test --features visit`.
+
+use derive_debug::CustomDebug;
+use derive_debug_runtime::{FieldVisitor, VisitFields};
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+#[debug(visit)]
+pub struct Request<T> {
+    method: &'static str,
+    #[debug = "0b{:08b}"]
+    flags: u8,
+    #[debug(redact)]
+    token: String,
+    #[debug(skip)]
+    scratch: Vec<u8>,
+    #[debug(len_only)]
+    body: Vec<u8>,
+    extra: T,
+}
+
+#[derive(CustomDebug)]
+#[debug(visit)]
+pub enum Event {
+    Start,
+    Data(u32, &'static str),
+    Stop { code: i32 },
+}
+
+// not marked, so this does not get a second VisitFields impl next to the
+// hand-written one.
+#[derive(CustomDebug)]
+pub struct Summary {
+    count: usize,
+}
+
+impl VisitFields for Summary {
+    fn visit_fields(&self, visitor: &mut dyn FieldVisitor) {
+        visitor.visit_field("total", &self.count);
+    }
+}
+
+struct Record(Vec<(String, String)>);
+
+impl FieldVisitor for Record {
+    fn visit_field(&mut self, name: &str, value: &dyn Debug) {
+        self.0.push((name.to_owned(), format!("{:?}", value)));
+    }
+}
+
+fn record<T: VisitFields>(value: &T) -> Vec<(String, String)> {
+    let mut record = Record(Vec::new());
+    value.visit_fields(&mut record);
+    record.0
+}
+
+fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn main() {
+    let request = Request {
+        method: "GET",
+        flags: 5,
+        token: "secret".to_owned(),
+        scratch: vec![1, 2],
+        body: vec![0; 3],
+        extra: Some(1),
+    };
+    assert_eq!(
+        record(&request),
+        pairs(&[
+            ("method", "\"GET\""),
+            ("flags", "0b00000101"),
+            ("token", "<redacted>"),
+            ("body", "3"),
+            ("extra", "Some(1)"),
+        ]),
+    );
+
+    assert_eq!(record(&Event::Start), pairs(&[]));
+    assert_eq!(record(&Event::Data(1, "x")), pairs(&[("0", "1"), ("1", "\"x\"")]));
+    assert_eq!(record(&Event::Stop { code: -1 }), pairs(&[("code", "-1")]));
+    assert_eq!(record(&Summary { count: 2 }), pairs(&[("total", "2")]));
+
+    // closures taking a name and a value are visitors too.
+    let mut names = Vec::new();
+    request.visit_fields(&mut |name: &str, _: &dyn Debug| names.push(name.to_owned()));
+    assert_eq!(names, ["method", "flags", "token", "body", "extra"]);
+}
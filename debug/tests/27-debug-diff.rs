@@ -0,0 +1,104 @@
+// When assert_eq! fails on a large struct, it prints two huge Debug blobs that
+// differ in a single field. With the `diff` feature, #[debug(diff)] makes
+// derive(CustomDebug) additionally implement derive_debug_runtime::DebugDiff,
+// whose debug_diff method lists only the fields that differ, one
+// `path: left != right` line each, or returns None if none do. Items without
+// the attribute only get Debug.
+//
+// Fields whose type is also marked #[debug(diff)] are compared field by field
+// and named by their dotted path. Other fields are compared with PartialEq, whose
+// bounds are inferred like the Debug bounds, and printed as in the Debug
+// output. Skipped and redacted fields, and fields of concrete types without
+// PartialEq, are not compared.
+//
+// Run with `cargo test --features diff`.
+
+use derive_debug::CustomDebug;
+use derive_debug_runtime::DebugDiff;
+
+#[derive(CustomDebug)]
+#[debug(diff)]
+pub struct Config<T> {
+    name: &'static str,
+    #[debug = "0x{:02x}"]
+    mode: u8,
+    limits: Limits,
+    window: Window,
+    #[debug(redact)]
+    password: &'static str,
+    #[debug(skip)]
+    cache: Vec<u8>,
+    extra: T,
+    callback: fn(),
+}
+
+#[derive(CustomDebug)]
+#[debug(diff)]
+pub struct Limits(u32, u32);
+
+// not marked, so compared as a whole with PartialEq.
+#[derive(CustomDebug, PartialEq)]
+pub struct Window(u32, u32);
+
+#[derive(CustomDebug, PartialEq)]
+#[debug(diff)]
+pub enum State {
+    Idle,
+    Running { pid: u32, limits: Limits },
+}
+
+impl PartialEq for Limits {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1
+    }
+}
+
+fn noop() {}
+
+fn config(mode: u8, limit: u32, extra: Option<i32>) -> Config<Option<i32>> {
+    config_with_window(mode, limit, extra, Window(80, 24))
+}
+
+fn config_with_window(mode: u8, limit: u32, extra: Option<i32>, window: Window) -> Config<Option<i32>> {
+    Config {
+        name: "server",
+        mode,
+        limits: Limits(1, limit),
+        window,
+        password: "secret",
+        cache: Vec::new(),
+        extra,
+        callback: noop,
+    }
+}
+
+fn main() {
+    assert_eq!(config(1, 10, None).debug_diff(&config(1, 10, None)), None);
+
+    let diff = config(1, 10, None).debug_diff(&config(0x1f, 20, Some(3)));
+    assert_eq!(
+        diff.as_deref(),
+        Some("mode: 0x01 != 0x1f\nlimits.1: 10 != 20\nextra: None != Some(3)"),
+    );
+
+    assert_eq!(
+        config(1, 10, None).debug_diff(&config_with_window(1, 10, None, Window(80, 25))).as_deref(),
+        Some("window: Window(80, 24) != Window(80, 25)"),
+    );
+
+    let mut other = config(1, 10, None);
+    other.password = "changed";
+    other.cache.push(1);
+    assert_eq!(config(1, 10, None).debug_diff(&other), None);
+
+    assert_eq!(State::Idle.debug_diff(&State::Idle), None);
+    assert_eq!(
+        State::Idle.debug_diff(&State::Running { pid: 1, limits: Limits(0, 0) }).as_deref(),
+        Some("Idle != Running { pid: 1, limits: Limits(0, 0) }"),
+    );
+    let running = State::Running { pid: 1, limits: Limits(0, 0) };
+    assert_eq!(
+        running.debug_diff(&State::Running { pid: 2, limits: Limits(0, 5) }).as_deref(),
+        Some("pid: 1 != 2\nlimits.1: 0 != 5"),
+    );
+}
@@ -0,0 +1,84 @@
+// Associated types are bounded as a whole wherever they appear, without
+// bounding the type parameter they belong to: qualified paths such as
+// <T as Trait>::Value, associated types of associated types such as
+// T::Inner::Value, and associated types nested in other types such as
+// Vec<T::Value>.
+//
+// Each distinct type gets its own bound, so a field of type T next to a field
+// of type T::Value produces both `T: Debug` and `T::Value: Debug`.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+    type Inner: Trait;
+}
+
+pub trait Convert<T> {
+    type Output;
+}
+
+#[derive(CustomDebug)]
+pub struct Qualified<T: Trait> {
+    value: <T as Trait>::Value,
+    values: Vec<T::Value>,
+    nested: <T::Inner as Trait>::Value,
+    deep: Option<T::Inner>,
+}
+
+#[derive(CustomDebug)]
+pub struct Both<T: Trait> {
+    key: T,
+    value: T::Value,
+}
+
+#[derive(CustomDebug)]
+pub struct ThroughTraitArgs<T>
+where
+    u8: Convert<T>,
+{
+    output: <u8 as Convert<T>>::Output,
+}
+
+impl Convert<bool> for u8 {
+    type Output = u16;
+}
+
+#[derive(Debug)]
+struct Leaf;
+
+struct Id;
+
+impl Trait for Leaf {
+    type Value = u8;
+    type Inner = Leaf;
+}
+
+impl Trait for Id {
+    type Value = &'static str;
+    type Inner = Leaf;
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    // `Id` does not implement Debug, only its associated types do.
+    assert_debug::<Qualified<Id>>();
+
+    let qualified: Qualified<Id> = Qualified {
+        value: "x",
+        values: vec!["y"],
+        nested: 1,
+        deep: None,
+    };
+    assert_eq!(
+        format!("{:?}", qualified),
+        r#"Qualified { value: "x", values: ["y"], nested: 1, deep: None }"#,
+    );
+
+    assert_debug::<Both<Leaf>>();
+    assert_eq!(format!("{:?}", Both { key: Leaf, value: 3u8 }), "Both { key: Leaf, value: 3 }");
+
+    assert_debug::<ThroughTraitArgs<bool>>();
+}
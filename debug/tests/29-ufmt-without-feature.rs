@@ -0,0 +1,13 @@
+// #[debug(ufmt)] asks for a ufmt::uDebug impl, which is only available with
+// the `ufmt` feature of derive_debug. Without it the attribute is an error
+// rather than being silently ignored.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(ufmt)]
+pub struct Reading {
+    value: i32,
+}
+
+fn main() {}
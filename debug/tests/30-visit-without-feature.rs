@@ -0,0 +1,13 @@
+// #[debug(visit)] asks for a VisitFields impl, which is only available with
+// the `visit` feature of derive_debug. Without it the attribute is an error
+// rather than being silently ignored.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(visit)]
+pub struct Reading {
+    value: i32,
+}
+
+fn main() {}
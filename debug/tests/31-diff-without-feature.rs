@@ -0,0 +1,13 @@
+// #[debug(diff)] asks for a DebugDiff impl, which is only available with the
+// `diff` feature of derive_debug. Without it the attribute is an error rather
+// than being silently ignored.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(diff)]
+pub struct Reading {
+    value: i32,
+}
+
+fn main() {}
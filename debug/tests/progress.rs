@@ -9,4 +9,35 @@ fn tests() {
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
     t.pass("tests/08-escape-hatch.rs");
-}
\ No newline at end of file
+    t.pass("tests/09-enum.rs");
+    t.pass("tests/10-tuple-and-unit-struct.rs");
+    t.pass("tests/11-skip-and-redact.rs");
+    t.pass("tests/12-custom-formatter.rs");
+    t.pass("tests/13-generics.rs");
+    t.pass("tests/14-bound-inference.rs");
+    t.pass("tests/15-field-bound.rs");
+    t.pass("tests/16-output-modes.rs");
+    t.compile_fail("tests/17-transparent-wrong.rs");
+    t.pass("tests/18-display.rs");
+    t.compile_fail("tests/19-display-unknown-field.rs");
+    t.pass("tests/20-collection-summary.rs");
+    t.pass("tests/21-no-std.rs");
+    t.compile_fail("tests/22-diagnostics.rs");
+    t.pass("tests/23-union.rs");
+    t.compile_fail("tests/24-union-tag-wrong.rs");
+    t.pass("tests/28-qualified-associated-type.rs");
+
+    #[cfg(feature = "ufmt")]
+    t.pass("tests/25-ufmt.rs");
+    #[cfg(not(feature = "ufmt"))]
+    t.compile_fail("tests/29-ufmt-without-feature.rs");
+    #[cfg(feature = "visit")]
+    t.pass("tests/26-visit-fields.rs");
+    #[cfg(not(feature = "visit"))]
+    t.compile_fail("tests/30-visit-without-feature.rs");
+    #[cfg(feature = "diff")]
+    t.pass("tests/27-debug-diff.rs");
+    #[cfg(not(feature = "diff"))]
+    t.compile_fail("tests/31-diff-without-feature.rs");
+}
+
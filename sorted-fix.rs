@@ -0,0 +1,518 @@
+// Rewrites the #[sorted] enums and match expressions of Rust source files in
+// the order #[sorted] expects, so that a long list does not have to be fixed by
+// hand one "should sort before" error at a time.
+//
+// Every variant or arm is moved together with the attributes, comments and
+// blank lines written before it, and a comment on the same line after it.
+// Names are compared by the same code as the #[sorted] macro, so the arguments
+// of #[sorted(...)] are honoured.
+//
+// To run it:
+//     $ cargo run --bin sorted-fix -- src/
+//
+// With --check nothing is written, and the exit code tells whether a list is
+// out of order.
+
+use std::{env, fs, io, path::{Path, PathBuf}, process::ExitCode};
+
+use proc_macro2::{extra::DelimSpan, LineColumn, Span};
+use syn::{spanned::Spanned, visit::Visit};
+
+// the spans of names are only read by the diagnostics of the macro.
+#[allow(dead_code)]
+#[path = "sorted/src/order.rs"]
+mod order;
+
+use order::{arm_names, enum_names, sorted_order, SortName, SortOptions};
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut paths: Vec<PathBuf> = Vec::default();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("usage: sorted-fix [--check] <file or directory>...");
+        return ExitCode::from(2);
+    }
+
+    let mut files: Vec<PathBuf> = Vec::default();
+    for path in paths.iter() {
+        if let Err(e) = collect_rust_files(path, &mut files) {
+            eprintln!("{}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut failed = false;
+    let mut unsorted = false;
+    for file in files.iter() {
+        let (changed, messages) = fix_file(file, check);
+        for message in messages.iter() {
+            eprintln!("{}", message);
+        }
+        failed |= !messages.is_empty();
+        if changed && check {
+            println!("{}: not sorted", file.display());
+            unsorted = true;
+        } else if changed {
+            println!("{}: sorted", file.display());
+        }
+    }
+
+    if failed || unsorted {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Collects `path` if it is a file, or else the `.rs` files under it, leaving
+/// out hidden and `target` directories.
+fn collect_rust_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?.map(|entry| entry.map(|e| e.path())).collect::<io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if entry.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_rust_files(&entry, files)?;
+            }
+        } else if name.ends_with(".rs") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Sorts the lists of `file`, writing it back unless `check` is set. Returns
+/// whether a list was out of order, and the messages of the lists that could
+/// not be sorted.
+fn fix_file(file: &Path, check: bool) -> (bool, Vec<String>) {
+    let text = match fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(e) => return (false, vec![format!("{}: {}", file.display(), e)]),
+    };
+
+    let (fixed, errors) = match fix_source(&text) {
+        Ok(fixed) => fixed,
+        Err(e) => return (false, vec![error_message(file, &e)]),
+    };
+    let mut messages: Vec<String> = errors.iter().map(|e| error_message(file, e)).collect();
+    if fixed == text {
+        return (false, messages);
+    }
+
+    if !check {
+        if let Err(e) = fs::write(file, fixed) {
+            messages.push(format!("{}: {}", file.display(), e));
+        }
+    }
+    (true, messages)
+}
+
+fn error_message(file: &Path, error: &syn::Error) -> String {
+    let start = error.span().start();
+    format!("{}:{}:{}: {}", file.display(), start.line, start.column + 1, error)
+}
+
+/// Sorts every `#[sorted]` list of `text`, returning the new text and the
+/// errors of the lists left alone.
+///
+/// A list nested in another one is sorted first and the text parsed again,
+/// until every list is sorted.
+fn fix_source(text: &str) -> syn::Result<(String, Vec<syn::Error>)> {
+    let mut text = text.to_owned();
+    let mut errors: Option<Vec<syn::Error>> = None;
+
+    loop {
+        let file = syn::parse_file(&text)?;
+        let source = Source::new(&text);
+        let mut visitor = FixVisitor { source: &source, edits: Vec::default(), errors: Vec::default() };
+        visitor.visit_file(&file);
+        // lists in error stay as they are, so they are reported once.
+        let errors = errors.get_or_insert(visitor.errors);
+
+        let mut edits: Vec<Edit> = Vec::default();
+        for edit in visitor.edits.iter() {
+            let has_inner = visitor.edits.iter().any(|inner| !std::ptr::eq(inner, edit) && edit.start <= inner.start && inner.end <= edit.end);
+            if !has_inner {
+                edits.push(edit.clone());
+            }
+        }
+        if edits.is_empty() {
+            return Ok((text, std::mem::take(errors)));
+        }
+
+        // the edits do not overlap, so applying them from the end keeps the
+        // offsets of the others valid.
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+        for edit in edits {
+            text.replace_range(edit.start..edit.end, &edit.replacement);
+        }
+    }
+}
+
+/// The text of a file with the offset of each of its lines.
+struct Source<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Source<'a> {
+    fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        Self { text, line_starts }
+    }
+
+    /// The byte offset of a line and column, which counts characters.
+    fn offset(&self, position: LineColumn) -> usize {
+        let line_start = self.line_starts[position.line - 1];
+        self.text[line_start..].char_indices().nth(position.column).map_or(self.text.len(), |(i, _)| line_start + i)
+    }
+
+    /// The end of the line following `offset` when the rest of it is a
+    /// comment, either `// ...` or a `/* ... */` closed on the same line, or
+    /// `offset` when there is none.
+    fn skip_trailing_comment(&self, offset: usize) -> usize {
+        let rest = &self.text[offset..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        let mut comment = line.trim_start();
+        if let Some(block) = comment.strip_prefix("/*") {
+            match block.find("*/") {
+                Some(close) => comment = block[close + 2..].trim_start(),
+                None => return offset,
+            }
+            if comment.is_empty() {
+                return offset + line.len();
+            }
+        }
+        if comment.starts_with("//") {
+            offset + line.len()
+        } else {
+            offset
+        }
+    }
+}
+
+/// Replaces the text from `start` to `end` with `replacement`.
+#[derive(Clone)]
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+/// The end of a variant or match arm, and of the comma following it if any.
+struct ElementEnd {
+    element: Span,
+    comma: Option<Span>,
+}
+
+struct FixVisitor<'a> {
+    source: &'a Source<'a>,
+    edits: Vec<Edit>,
+    errors: Vec<syn::Error>,
+}
+
+impl<'ast> Visit<'ast> for FixVisitor<'_> {
+
+    fn visit_item_enum(&mut self, item: &'ast syn::ItemEnum) {
+        if let Some(attr) = sorted_attr(&item.attrs) {
+            let fixed = SortOptions::from_attr(attr).and_then(|options| {
+                let names = enum_names(item, &options)?;
+                let ends: Vec<ElementEnd> = item.variants.pairs().map(|pair| ElementEnd {
+                    element: pair.value().span(),
+                    comma: pair.punct().map(|comma| comma.span),
+                }).collect();
+                let edit = self.fix_list(&item.brace_token.span, &names, &ends, &options);
+                // moving a variant would change the implicit discriminants
+                // following an explicit one.
+                let explicit = item.variants.iter().any(|v| v.discriminant.is_some());
+                let implicit = item.variants.iter().find(|v| v.discriminant.is_none());
+                if let (Some(_), true, Some(variant)) = (&edit, explicit, implicit) {
+                    return Err(syn::Error::new(variant.ident.span(), "sorted-fix does not reorder enums mixing explicit and implicit discriminants, as that changes their values"));
+                }
+                Ok(edit)
+            });
+            self.push(fixed);
+        }
+
+        syn::visit::visit_item_enum(self, item);
+    }
+
+    fn visit_expr_match(&mut self, exp: &'ast syn::ExprMatch) {
+        if let Some(attr) = sorted_attr(&exp.attrs) {
+            let fixed = SortOptions::from_attr(attr).and_then(|options| {
+                let names = arm_names(&exp.arms, &options)?;
+                let ends: Vec<ElementEnd> = exp.arms.iter().map(|arm| ElementEnd {
+                    element: arm.span(),
+                    comma: arm.comma.map(|comma| comma.span),
+                }).collect();
+                Ok(self.fix_list(&exp.brace_token.span, &names, &ends, &options))
+            });
+            self.push(fixed);
+        }
+
+        syn::visit::visit_expr_match(self, exp);
+    }
+
+}
+
+impl FixVisitor<'_> {
+
+    fn push(&mut self, fixed: syn::Result<Option<Edit>>) {
+        match fixed {
+            Ok(Some(edit)) => self.edits.push(edit),
+            Ok(None) => {},
+            Err(e) => self.errors.push(e),
+        }
+    }
+
+    /// The edit writing the elements between the braces `brace` in sorted
+    /// order, or `None` when they already are.
+    ///
+    /// Each element takes the text from the end of the previous one up to its
+    /// comma and a comment on the same line. A comma is added after the last
+    /// element if it has none, as it may not stay last.
+    fn fix_list(&self, brace: &DelimSpan, names: &[SortName], ends: &[ElementEnd], options: &SortOptions) -> Option<Edit> {
+        let order = sorted_order(names, options);
+        if order.iter().enumerate().all(|(position, &index)| position == index) {
+            return None;
+        }
+
+        // a comment after the brace describes the whole list, so it stays put.
+        let start = self.source.skip_trailing_comment(self.source.offset(brace.open().end()));
+        let mut segments: Vec<String> = Vec::default();
+        let mut end = start;
+        for (index, element_end) in ends.iter().enumerate() {
+            let segment_start = end;
+            let mut segment = match element_end.comma {
+                Some(comma) => {
+                    end = self.source.offset(comma.end());
+                    self.source.text[segment_start..end].to_owned()
+                },
+                None => {
+                    end = self.source.offset(element_end.element.end());
+                    let text = &self.source.text[segment_start..end];
+                    if index == ends.len() - 1 {
+                        format!("{},", text)
+                    } else {
+                        text.to_owned()
+                    }
+                },
+            };
+            let comment_end = self.source.skip_trailing_comment(end);
+            segment.push_str(&self.source.text[end..comment_end]);
+            end = comment_end;
+            segments.push(segment);
+        }
+
+        let replacement: String = order.into_iter().map(|index| segments[index].as_str()).collect();
+        Some(Edit { start, end, replacement })
+    }
+
+}
+
+/// The `#[sorted]` or `#[sorted::sorted]` attribute among `attrs`.
+fn sorted_attr(attrs: &[syn::Attribute]) -> Option<&syn::Attribute> {
+    attrs.iter().find(|attr| {
+        let segments: Vec<String> = attr.path().segments.iter().map(|s| s.ident.to_string()).collect();
+        segments == ["sorted"] || segments == ["sorted", "sorted"]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fixed text and the messages of the lists left alone.
+    fn fix(text: &str) -> (String, Vec<String>) {
+        let (fixed, errors) = fix_source(text).unwrap();
+        (fixed, errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn keeps_attributes_and_comments_with_variants() {
+        let text = "\
+#[sorted]
+pub enum Error {
+    // must come last
+    Zlib(String), // trailing
+    Config,
+
+    /// Database errors.
+    #[allow(dead_code)]
+    Database { code: u32 },
+    Auth,
+}
+";
+        let expected = "\
+#[sorted]
+pub enum Error {
+    Auth,
+    Config,
+
+    /// Database errors.
+    #[allow(dead_code)]
+    Database { code: u32 },
+    // must come last
+    Zlib(String), // trailing
+}
+";
+        assert_eq!(fix(text), (expected.to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn keeps_the_comment_after_the_brace_in_place() {
+        let text = "#[sorted]\nenum E { // the errors\n    B, /* b */\n    A,\n}\n";
+        let expected = "#[sorted]\nenum E { // the errors\n    A,\n    B, /* b */\n}\n";
+        assert_eq!(fix(text), (expected.to_owned(), Vec::new()));
+
+        let text = "#[sorted]\nenum E { /* the errors */\n    B,\n    A,\n}\n";
+        let expected = "#[sorted]\nenum E { /* the errors */\n    A,\n    B,\n}\n";
+        assert_eq!(fix(text), (expected.to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn moves_trailing_block_comments_with_elements() {
+        let text = "#[sorted]\nenum E {\n    B, /* b */\n    A, /* a */ // and more\n    C,\n}\n";
+        let expected = "#[sorted]\nenum E {\n    A, /* a */ // and more\n    B, /* b */\n    C,\n}\n";
+        assert_eq!(fix(text), (expected.to_owned(), Vec::new()));
+
+        // a block comment followed by the next element belongs to that element.
+        let text = "#[sorted]\nenum E { B, /* a */ A }\n";
+        assert_eq!(fix(text).0, "#[sorted]\nenum E { /* a */ A, B, }\n");
+    }
+
+    #[test]
+    fn adds_a_comma_after_the_last_element() {
+        let text = "#[sorted]\nenum Letter {\n    B,\n    A\n}\n";
+        let expected = "#[sorted]\nenum Letter {\n    A,\n    B,\n}\n";
+        assert_eq!(fix(text), (expected.to_owned(), Vec::new()));
+
+        let text = "#[sorted]\nenum Letter { B, A }\n";
+        assert_eq!(fix(text).0, "#[sorted]\nenum Letter { A, B, }\n");
+    }
+
+    #[test]
+    fn sorts_nested_matches() {
+        let text = "\
+fn f(e: Error) -> u8 {
+    #[sorted]
+    match e {
+        Error::Zlib => {
+            #[sorted]
+            match 1 {
+                b => 2,
+                a => 1,
+            }
+        }
+        Error::Auth => 2,
+        _ => 0
+    }
+}
+";
+        let expected = "\
+fn f(e: Error) -> u8 {
+    #[sorted]
+    match e {
+        Error::Auth => 2,
+        Error::Zlib => {
+            #[sorted]
+            match 1 {
+                a => 1,
+                b => 2,
+            }
+        }
+        _ => 0,
+    }
+}
+";
+        assert_eq!(fix(text), (expected.to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn honours_sort_options() {
+        let text = "#[sorted(natural, reverse)]\nenum Version { Http1, Http10, Http2 }\n";
+        assert_eq!(fix(text).0, "#[sorted(natural, reverse)]\nenum Version { Http10, Http2, Http1, }\n");
+
+        let text = "#[sorted(by_discriminant)]\nenum Code { A = 3, B = 1 }\n";
+        assert_eq!(fix(text).0, "#[sorted(by_discriminant)]\nenum Code { B = 1, A = 3, }\n");
+    }
+
+    #[test]
+    fn leaves_sorted_and_unmarked_lists_alone() {
+        let text = "#[sorted]\nenum Sorted { A, B }\n\nenum Unmarked { B, A }\n";
+        assert_eq!(fix(text), (text.to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn refuses_to_change_implicit_discriminants() {
+        let text = "#[sorted]\nenum Disc { Z = 5, Y, X }\n";
+        let (fixed, errors) = fix(text);
+        assert_eq!(fixed, text);
+        assert_eq!(errors, ["sorted-fix does not reorder enums mixing explicit and implicit discriminants, as that changes their values"]);
+
+        let text = "#[sorted(by_discriminant)]\nenum Disc { A = 3, B, C = 1 }\n";
+        let (fixed, errors) = fix(text);
+        assert_eq!(fixed, text);
+        assert_eq!(errors.len(), 1);
+
+        // an already sorted enum is not reported.
+        let text = "#[sorted]\nenum Disc { A = 1, B }\n";
+        assert_eq!(fix(text), (text.to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn reports_lists_it_cannot_sort_and_fixes_the_others() {
+        let text = "\
+#[sorted(alphabetical)]
+enum Unknown { B, A }
+
+#[sorted]
+enum Letter { B, A }
+
+fn f(x: (u8, u8)) {
+    #[sorted]
+    match x {
+        (1, _) => {}
+        _ => {}
+    }
+}
+";
+        let (fixed, errors) = fix(text);
+        assert!(fixed.contains("enum Unknown { B, A }"));
+        assert!(fixed.contains("enum Letter { A, B, }"));
+        assert_eq!(errors, ["expected one of: case_insensitive, natural, reverse, by_discriminant", "unsupported by #[sorted]"]);
+    }
+
+    #[test]
+    fn fails_on_invalid_rust() {
+        assert!(fix_source("#[sorted]\nenum Broken {").is_err());
+    }
+
+    #[test]
+    fn writes_the_file_unless_checking() {
+        let file = env::temp_dir().join(format!("sorted-fix-{}.rs", std::process::id()));
+        let text = "#[sorted]\nenum Letter { B, A }\n";
+        fs::write(&file, text).unwrap();
+
+        assert_eq!(fix_file(&file, true), (true, Vec::new()));
+        assert_eq!(fs::read_to_string(&file).unwrap(), text);
+
+        assert_eq!(fix_file(&file, false), (true, Vec::new()));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "#[sorted]\nenum Letter { A, B, }\n");
+
+        assert_eq!(fix_file(&file, true), (false, Vec::new()));
+        fs::remove_file(&file).unwrap();
+    }
+}
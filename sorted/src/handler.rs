@@ -1,208 +1,284 @@
-use std::cmp::Ordering;
-
 use proc_macro2::{TokenStream, Span};
 use syn::{parse::Parse, __private::ToTokens, visit_mut::VisitMut, spanned::Spanned};
 
+use crate::order::{arm_names, enum_names, sorted_order, SortName, SortOptions};
+
 
 
 pub struct SortedStruct {
-    enum_item: syn::ItemEnum
+    item: syn::Item
 }
 
 impl Parse for SortedStruct {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let enum_item = input.parse::<syn::ItemEnum>();
-        if enum_item.is_err() {
-            return Err(syn::Error::new(Span::call_site(), "expected enum or match expression"));
-        }
-        let enum_item = enum_item.unwrap();
+        let item = match input.parse::<syn::Item>() {
+            Ok(item) => item,
+            Err(_) => return Err(unsupported_item_error()),
+        };
 
-        let mut errors: Vec<_> = Vec::default();
-        let mut variants: Vec<&syn::Variant> = enum_item.variants.iter().collect();
-        variants.sort_by(|a, b| {
-            
+        Ok(Self { item })
+    }
+}
 
-            let ret = a.ident.cmp(&b.ident);
-            if ret.is_lt() {
-                let msg = format!("{} should sort before {}", a.ident.to_string(), b.ident.to_string());
-                errors.push(syn::Error::new(a.span(), msg));
-            }
-            ret
-        });
+impl SortedStruct {
 
-        if !errors.is_empty() {
-            let first_error = errors.pop();
-            if let Some(e) = first_error {
-                return Err(e);
+    pub fn check(&self, options: &SortOptions) -> syn::Result<()> {
+        let mut error: Option<syn::Error> = None;
+        for names in item_names(&self.item, options)? {
+            if let Err(e) = check_sorted(&names, options) {
+                match error.as_mut() {
+                    Some(error) => error.combine(e),
+                    None => error = Some(e),
+                }
             }
         }
 
-        Ok(Self { enum_item })
+        match error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    pub fn into_token_stream(self) -> TokenStream {
+        self.item.into_token_stream()
     }
 }
 
-impl SortedStruct {
+fn unsupported_item_error() -> syn::Error {
+    syn::Error::new(Span::call_site(), "expected enum, struct, impl, mod, use or match expression")
+}
 
-    pub fn to_token_stream(self) -> TokenStream {
-        self.enum_item.into_token_stream()
+/// The lists of names that `#[sorted]` checks in `item`: the variants of an
+/// enum, the fields of a struct, the named items of an impl block or inline
+/// module, and every group of a use tree.
+fn item_names(item: &syn::Item, options: &SortOptions) -> syn::Result<Vec<Vec<SortName>>> {
+    if options.by_discriminant && !matches!(item, syn::Item::Enum(_)) {
+        return Err(syn::Error::new(Span::call_site(), "#[sorted(by_discriminant)] is only supported on enums"));
     }
-}
 
+    let names = match item {
+        syn::Item::Enum(item) => enum_names(item, options)?,
+        syn::Item::Struct(item) => {
+            match &item.fields {
+                syn::Fields::Named(fields) => fields.named.iter().filter_map(|f| f.ident.as_ref()).map(SortName::new).collect(),
+                fields => return Err(syn::Error::new(fields.span(), "#[sorted] requires a struct with named fields")),
+            }
+        },
+        syn::Item::Impl(item) => {
+            item.items.iter().filter_map(impl_item_ident).map(SortName::new).collect()
+        },
+        syn::Item::Mod(item) => {
+            match &item.content {
+                Some((_, items)) => items.iter().filter_map(item_ident).map(SortName::new).collect(),
+                None => return Err(syn::Error::new(item.ident.span(), "#[sorted] requires a module with inline items")),
+            }
+        },
+        syn::Item::Use(item) => {
+            let mut groups: Vec<Vec<SortName>> = Vec::default();
+            use_tree_groups(&item.tree, &mut groups);
+            return Ok(groups);
+        },
+        _ => return Err(unsupported_item_error()),
+    };
+
+    Ok(vec![names])
+}
 
-pub struct CheckStruct {
-    fn_item: syn::ItemFn
+/// The name of an item inside an impl block. Macro invocations have none and
+/// are not checked.
+fn impl_item_ident(item: &syn::ImplItem) -> Option<&syn::Ident> {
+    match item {
+        syn::ImplItem::Const(item) => Some(&item.ident),
+        syn::ImplItem::Fn(item) => Some(&item.sig.ident),
+        syn::ImplItem::Type(item) => Some(&item.ident),
+        _ => None,
+    }
 }
 
+/// The name of an item inside a module. Use declarations, impl blocks and
+/// other unnamed items are not checked.
+fn item_ident(item: &syn::Item) -> Option<&syn::Ident> {
+    match item {
+        syn::Item::Const(item) => Some(&item.ident),
+        syn::Item::Enum(item) => Some(&item.ident),
+        syn::Item::ExternCrate(item) => Some(&item.ident),
+        syn::Item::Fn(item) => Some(&item.sig.ident),
+        syn::Item::Macro(item) => item.ident.as_ref(),
+        syn::Item::Mod(item) => Some(&item.ident),
+        syn::Item::Static(item) => Some(&item.ident),
+        syn::Item::Struct(item) => Some(&item.ident),
+        syn::Item::Trait(item) => Some(&item.ident),
+        syn::Item::TraitAlias(item) => Some(&item.ident),
+        syn::Item::Type(item) => Some(&item.ident),
+        syn::Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
 
-impl Parse for CheckStruct {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-       let fn_item = input.parse()?;
-       Ok(Self { fn_item })
+/// Collects the names of every `{...}` group in `tree`, nested groups
+/// included. Each group is sorted on its own.
+fn use_tree_groups(tree: &syn::UseTree, groups: &mut Vec<Vec<SortName>>) {
+    match tree {
+        syn::UseTree::Path(path) => use_tree_groups(&path.tree, groups),
+        syn::UseTree::Group(group) => {
+            let mut names: Vec<SortName> = Vec::default();
+            for tree in group.items.iter() {
+                let name = match tree {
+                    syn::UseTree::Path(path) => SortName::new(&path.ident),
+                    syn::UseTree::Name(name) => SortName::new(&name.ident),
+                    syn::UseTree::Rename(rename) => SortName::new(&rename.ident),
+                    syn::UseTree::Glob(glob) => SortName { name: "*".to_owned(), span: glob.star_token.span, discriminant: None },
+                    // a group nested directly in a group has no name of its own.
+                    syn::UseTree::Group(_) => {
+                        use_tree_groups(tree, groups);
+                        continue;
+                    },
+                };
+                names.push(name);
+                use_tree_groups(tree, groups);
+            }
+            groups.push(names);
+        },
+        syn::UseTree::Name(_) | syn::UseTree::Rename(_) | syn::UseTree::Glob(_) => {},
     }
 }
 
-struct CheckVisitMut(Vec<syn::Error>);
+/// Reports every misplaced name, all in one error. The names kept in place are
+/// a longest run of names already in order, the earliest one when there are
+/// several, and each other name is reported against the kept name it should
+/// be moved next to.
+fn check_sorted(names: &[SortName], options: &SortOptions) -> syn::Result<()> {
+    let kept = sorted_subsequence(names, options);
+
+    let mut error: Option<syn::Error> = None;
+    // the first error also shows the whole list in order.
+    let mut help = Some(sorted_names_help(names, options));
+    for (index, current) in names.iter().enumerate() {
+        if kept.contains(&index) {
+            continue;
+        }
+        let before = kept.iter().find(|&&k| options.cmp(current, &names[k]).is_lt());
+        let msg = match before {
+            Some(&k) if k < index => format!("{} should sort before {}", current.name, names[k].name),
+            _ => {
+                // the name comes too early: it belongs after the last kept name
+                // that does not sort after it, which is further down the list.
+                let after = kept.iter().rev().find(|&&k| options.cmp(current, &names[k]).is_ge());
+                match after {
+                    Some(&k) => format!("{} should sort after {}", current.name, names[k].name),
+                    None => continue,
+                }
+            },
+        };
+        let msg = match help.take() {
+            Some(help) => format!("{}\n{}", msg, help),
+            None => msg,
+        };
+        let e = syn::Error::new(current.span, msg);
+        match error.as_mut() {
+            Some(error) => error.combine(e),
+            None => error = Some(e),
+        }
+    }
 
-impl VisitMut for CheckVisitMut {
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
 
-    fn visit_item_fn_mut(&mut self, item: &mut syn::ItemFn) {
-        let body = item.block.as_mut();
-        let stmts_iter_mut = body.stmts.iter_mut();
-
-        for stmt in stmts_iter_mut {
-            match stmt {
-                syn::Stmt::Expr(exp, _) => {
-                    if let syn::Expr::Match(exp) = exp  {
-                        let vec: Vec<usize> = exp.attrs.iter().enumerate().filter(|(_, attr)| {
-                            let ident = attr.path().get_ident();
-                            if ident.is_none() {
-                                return false;
-                            }
-                            let ident = ident.unwrap();
-                            let ident_str = ident.to_string();
-                            return ident_str.eq("sorted");
-                        }).map(|(idx, _)|idx).collect();
-
-                        
-                        
-                        if !vec.is_empty() {
-                            exp.arms.sort_by(|a, b| {
-                                let a_pat: &syn::Pat = &a.pat;
-                                let b_pat: &syn::Pat = &b.pat;
-                                let ret = cmp_pat(a_pat, b_pat);
-
-                                if ret.is_none() {
-                                    self.0.push(syn::Error::new(b.span(), "unsupported by #[sorted]"));
-                                    return Ordering::Equal;
-                                }
-
-                                let (ordering, a_str, b_str) = ret.unwrap();
-
-                              
-                                if ordering.is_lt() {
-                                    let msg = format!("{} should sort before {}", a_str, b_str);
-                                    self.0.push(syn::Error::new(a.span(), msg));
-                                }
-                                ordering
-                            })
-                        }
-
-                        for idx in vec {
-                            exp.attrs.remove(idx);
-                        }
-                    }
-                },
-                _ => {}
+/// A help line listing `names` in the order they should be written in.
+fn sorted_names_help(names: &[SortName], options: &SortOptions) -> String {
+    let sorted: Vec<&str> = sorted_order(names, options).into_iter().map(|index| names[index].name.as_str()).collect();
+    format!("help: sort them as: {}", sorted.join(", "))
+}
+
+/// The indices of a longest subsequence of `names` that is already sorted.
+/// Among subsequences of the same length the one with the earliest indices is
+/// chosen, so that of two swapped names the second one is reported.
+fn sorted_subsequence(names: &[SortName], options: &SortOptions) -> Vec<usize> {
+    // lengths[i] is the length of the longest sorted subsequence starting at i.
+    let mut lengths: Vec<usize> = vec![1; names.len()];
+    for i in (0..names.len()).rev() {
+        for j in i + 1..names.len() {
+            if options.cmp(&names[i], &names[j]).is_le() {
+                lengths[i] = lengths[i].max(lengths[j] + 1);
             }
         }
     }
 
+    let mut kept: Vec<usize> = Vec::default();
+    let mut length = lengths.iter().copied().max().unwrap_or_default();
+    for (index, name) in names.iter().enumerate() {
+        if length == 0 {
+            break;
+        }
+        let follows = kept.last().is_none_or(|&last| options.cmp(&names[last], name).is_le());
+        if lengths[index] == length && follows {
+            kept.push(index);
+            length -= 1;
+        }
+    }
+    kept
 }
 
 
-impl CheckStruct {
-    pub fn to_token_stream(mut self) -> TokenStream {
-        let fn_item =&mut self.fn_item;
-        
-    
-        let mut check_visit_mut = CheckVisitMut(Vec::default());
-        
-        check_visit_mut.visit_item_fn_mut(fn_item);
 
-        let errors = check_visit_mut.0;
-        
-        let error = errors.into_iter().next();
+pub struct CheckStruct {
+    item: syn::Item
+}
 
-        if let Some(e) = error {
-            let mut token_stream = fn_item.into_token_stream();
-            token_stream.extend(e.into_compile_error());
-            token_stream
-        } else {
-            fn_item.into_token_stream()
-        }
+
+impl Parse for CheckStruct {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+       let item = input.parse()?;
+       match item {
+           syn::Item::Fn(_) | syn::Item::Impl(_) | syn::Item::Mod(_) => Ok(Self { item }),
+           _ => Err(syn::Error::new(Span::call_site(), "#[sorted::check] expects a function, impl block or module")),
+       }
     }
 }
 
-fn cmp_pat(a: &syn::Pat, b: &syn::Pat) -> Option<(Ordering, String, String)> {
-    let a = pat_to_string(a);
-    if a.is_none() {
-        return None;
-    }
+/// Checks and removes the `#[sorted]` attribute of every match expression,
+/// however deeply it is nested in the checked item.
+struct CheckVisitMut(Vec<syn::Error>);
 
-    let a = a.unwrap();
-    let b = pat_to_string(b);
-    if b.is_none() {
-        return None;
-    }
-    let b = b.unwrap();
+impl VisitMut for CheckVisitMut {
 
-    if a.ne(&b) && (a.eq("_") || b.eq("_")) {
-        if a.eq("_") {
-            return Some((Ordering::Greater, a, b));
-        } 
-        return Some((Ordering::Less, a, b));
+    fn visit_expr_match_mut(&mut self, exp: &mut syn::ExprMatch) {
+        let sorted_attr = exp.attrs.iter().position(|attr| attr.path().is_ident("sorted"));
+        if let Some(index) = sorted_attr {
+            let attr = exp.attrs.remove(index);
+            let checked = SortOptions::from_attr(&attr).and_then(|options| check_match_arms(&exp.arms, &options));
+            if let Err(e) = checked {
+                self.0.push(e);
+            }
+        }
+
+        // matches in the arms and the scrutinee are checked too.
+        syn::visit_mut::visit_expr_match_mut(self, exp);
     }
-    
 
-    Some((a.cmp(&b), a, b))
 }
 
-fn pat_to_string(pat: &syn::Pat) -> Option<String> {
-    match pat {
-        syn::Pat::TupleStruct(p) => {
+/// Checks that the arms of a `#[sorted]` match are sorted by their patterns.
+fn check_match_arms(arms: &[syn::Arm], options: &SortOptions) -> syn::Result<()> {
+    let names = arm_names(arms, options)?;
+    check_sorted(&names, options)
+}
 
-           Some(path_to_string(&p.path))
 
-        },
-        syn::Pat::Struct(p) => {
-            Some(path_to_string(&p.path))
-        }
-        syn::Pat::Path(p) => {
-            Some(path_to_string(&p.path))
-        }
-        syn::Pat::Wild(_) => {
-            Some("_".to_string())   
-        },
-        syn::Pat::Ident(ident) => {
-            Some(ident.ident.to_string())
+impl CheckStruct {
+    pub fn into_token_stream(mut self) -> TokenStream {
+        let item = &mut self.item;
+
+        let mut check_visit_mut = CheckVisitMut(Vec::default());
+        check_visit_mut.visit_item_mut(item);
+
+        let mut token_stream = item.into_token_stream();
+        for e in check_visit_mut.0 {
+            token_stream.extend(e.into_compile_error());
         }
-        _ => None
+        token_stream
     }
 }
-
-fn path_to_string(path: &syn::Path) -> String {
-    let mut ret = String::default(); 
-    let segments = &path.segments;
-    let pairs = segments.pairs();
-    pairs.for_each(|pair| {
-         let value = pair.value();
-         let value = value.ident.to_string();
-         let punct = pair.punct();
-
-         ret.push_str(value.as_str());
-         if punct.is_some() {
-             ret.push_str("::");
-         }
-    });
-    ret
-}
\ No newline at end of file
@@ -2,12 +2,18 @@ use proc_macro::TokenStream;
 use syn::parse_macro_input;
 
 mod handler;
+mod order;
 
 #[proc_macro_attribute]
-pub fn sorted(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn sorted(args: TokenStream, input: TokenStream) -> TokenStream {
+   let options = parse_macro_input!(args as order::SortOptions);
    let sorted_struct = parse_macro_input!(input as handler::SortedStruct);
 
-   sorted_struct.to_token_stream().into()
+   if let Err(e) = sorted_struct.check(&options) {
+      return e.into_compile_error().into();
+   }
+
+   sorted_struct.into_token_stream().into()
 }
 
 
@@ -15,5 +21,5 @@ pub fn sorted(_args: TokenStream, input: TokenStream) -> TokenStream {
 pub fn check(_args: TokenStream, input: TokenStream) -> TokenStream {
    let check_struct = parse_macro_input!(input as handler::CheckStruct);
 
-   check_struct.to_token_stream().into()
+   check_struct.into_token_stream().into()
 }
@@ -0,0 +1,227 @@
+//! How `#[sorted]` orders names. This module is shared with the `sorted-fix`
+//! binary of the workshop crate, so that the lists it rewrites are sorted
+//! exactly the way the macros check them.
+
+use std::cmp::Ordering;
+
+use proc_macro2::Span;
+use syn::{parse::Parse, spanned::Spanned};
+
+/// The arguments of `#[sorted(...)]`, changing how names are compared.
+#[derive(Default)]
+pub struct SortOptions {
+    pub(crate) case_insensitive: bool,
+    pub(crate) natural: bool,
+    pub(crate) reverse: bool,
+    pub(crate) by_discriminant: bool,
+}
+
+impl Parse for SortOptions {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        let idents = syn::punctuated::Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated(input)?;
+        for ident in idents {
+            match ident.to_string().as_str() {
+                "case_insensitive" => options.case_insensitive = true,
+                "natural" => options.natural = true,
+                "reverse" => options.reverse = true,
+                "by_discriminant" => options.by_discriminant = true,
+                _ => return Err(syn::Error::new(ident.span(), "expected one of: case_insensitive, natural, reverse, by_discriminant")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl SortOptions {
+
+    /// The options of a `#[sorted]` or `#[sorted(...)]` attribute.
+    pub(crate) fn from_attr(attr: &syn::Attribute) -> syn::Result<Self> {
+        match attr.meta {
+            syn::Meta::Path(_) => Ok(Self::default()),
+            _ => attr.parse_args(),
+        }
+    }
+
+    /// Compares two names, the wildcard pattern `_` sorting after everything
+    /// whatever the options.
+    pub(crate) fn cmp(&self, a: &SortName, b: &SortName) -> Ordering {
+        let ordering = match (a.name == "_", b.name == "_") {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) if self.by_discriminant => a.discriminant.cmp(&b.discriminant),
+            (false, false) if self.natural => cmp_natural(&self.fold_case(&a.name), &self.fold_case(&b.name)),
+            (false, false) => self.fold_case(&a.name).cmp(&self.fold_case(&b.name)),
+        };
+
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn fold_case(&self, name: &str) -> String {
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name.to_owned()
+        }
+    }
+}
+
+/// Compares two names with the runs of digits in them compared as numbers,
+/// so that `Http2` sorts before `Http10`.
+fn cmp_natural(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        let (a_char, b_char) = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) => (*a_char, *b_char),
+        };
+
+        if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let a_digits = take_digits(&mut a_chars);
+            let b_digits = take_digits(&mut b_chars);
+            let a_number = a_digits.trim_start_matches('0');
+            let b_number = b_digits.trim_start_matches('0');
+            let ordering = a_number.len().cmp(&b_number.len())
+                .then_with(|| a_number.cmp(b_number))
+                .then_with(|| a_digits.len().cmp(&b_digits.len()));
+            if ordering.is_ne() {
+                return ordering;
+            }
+        } else {
+            let ordering = a_char.cmp(&b_char);
+            if ordering.is_ne() {
+                return ordering;
+            }
+            a_chars.next();
+            b_chars.next();
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// A name in a list checked by `#[sorted]`, with the span its errors point at.
+/// Enum variants also carry their discriminant for `by_discriminant`.
+pub(crate) struct SortName {
+    pub(crate) name: String,
+    pub(crate) span: Span,
+    pub(crate) discriminant: Option<i128>,
+}
+
+impl SortName {
+    pub(crate) fn new(ident: &syn::Ident) -> Self {
+        Self {
+            name: ident.to_string(),
+            span: ident.span(),
+            discriminant: None,
+        }
+    }
+}
+
+/// The names of the variants of an enum, with their discriminants.
+pub(crate) fn enum_names(item: &syn::ItemEnum, options: &SortOptions) -> syn::Result<Vec<SortName>> {
+    let mut names: Vec<SortName> = Vec::default();
+    // variants without an explicit discriminant follow the previous one.
+    let mut next_discriminant: i128 = 0;
+    for variant in item.variants.iter() {
+        let discriminant = match &variant.discriminant {
+            Some((_, expr)) if options.by_discriminant => parse_discriminant(expr)?,
+            _ => next_discriminant,
+        };
+        next_discriminant = discriminant.wrapping_add(1);
+        names.push(SortName {
+            discriminant: Some(discriminant),
+            ..SortName::new(&variant.ident)
+        });
+    }
+    Ok(names)
+}
+
+/// The value of an integer literal discriminant such as `4` or `-1`.
+fn parse_discriminant(expr: &syn::Expr) -> syn::Result<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => lit.base10_parse::<i128>(),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => Ok(-parse_discriminant(expr)?),
+        syn::Expr::Group(group) => parse_discriminant(&group.expr),
+        syn::Expr::Paren(paren) => parse_discriminant(&paren.expr),
+        _ => Err(syn::Error::new(expr.span(), "#[sorted(by_discriminant)] requires integer literal discriminants")),
+    }
+}
+
+/// The names of the arms of a `#[sorted]` match.
+pub(crate) fn arm_names(arms: &[syn::Arm], options: &SortOptions) -> syn::Result<Vec<SortName>> {
+    if options.by_discriminant {
+        return Err(syn::Error::new(Span::call_site(), "#[sorted(by_discriminant)] is only supported on enums"));
+    }
+
+    let mut names: Vec<SortName> = Vec::default();
+    for arm in arms {
+        match pat_to_string(&arm.pat) {
+            Some(name) => names.push(SortName { name, span: arm.pat.span(), discriminant: None }),
+            None => return Err(syn::Error::new(arm.pat.span(), "unsupported by #[sorted]")),
+        }
+    }
+    Ok(names)
+}
+
+/// The indices of `names` in sorted order. Equal names keep their order.
+pub(crate) fn sorted_order(names: &[SortName], options: &SortOptions) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..names.len()).collect();
+    order.sort_by(|&a, &b| options.cmp(&names[a], &names[b]));
+    order
+}
+
+/// The name a match arm is sorted by: the path of its pattern, the bound
+/// identifier or `_`. Other patterns are not supported.
+pub(crate) fn pat_to_string(pat: &syn::Pat) -> Option<String> {
+    match pat {
+        syn::Pat::TupleStruct(p) => {
+            Some(path_to_string(&p.path))
+        },
+        syn::Pat::Struct(p) => {
+            Some(path_to_string(&p.path))
+        }
+        syn::Pat::Path(p) => {
+            Some(path_to_string(&p.path))
+        }
+        syn::Pat::Wild(_) => {
+            Some("_".to_string())
+        },
+        syn::Pat::Ident(ident) => {
+            Some(ident.ident.to_string())
+        }
+        _ => None
+    }
+}
+
+fn path_to_string(path: &syn::Path) -> String {
+    let mut ret = String::default();
+    let segments = &path.segments;
+    let pairs = segments.pairs();
+    pairs.for_each(|pair| {
+         let value = pair.value();
+         let value = value.ident.to_string();
+         let punct = pair.punct();
+
+         ret.push_str(value.as_str());
+         if punct.is_some() {
+             ret.push_str("::");
+         }
+    });
+    ret
+}
@@ -1,8 +1,9 @@
-// The #[sorted] macro is only defined to work on enum types, so this is a test
-// to ensure that when it's attached to a struct (or anything else) it produces
-// some reasonable error. Your macro will need to look into the syn::Item that
-// it parsed to ensure that it represents an enum, returning an error for any
-// other type of Item such as a struct.
+// The #[sorted] macro is only defined to work on lists that have an order: the
+// variants of an enum, the fields of a struct, the items of an impl block or
+// module, and use groups. This is a test to ensure that when it's attached to
+// anything else, such as a function, it produces some reasonable error. Your
+// macro will need to look into the syn::Item that it parsed to ensure that it
+// represents one of those, returning an error for any other type of Item.
 //
 // This is an exercise in exploring how to return errors from procedural macros.
 // The goal is to produce an understandable error message which is tailored to
@@ -29,9 +30,8 @@
 use sorted::sorted;
 
 #[sorted]
-pub struct Error {
-    kind: ErrorKind,
-    message: String,
+pub fn error() -> ErrorKind {
+    ErrorKind::Io
 }
 
 enum ErrorKind {
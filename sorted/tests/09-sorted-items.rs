@@ -0,0 +1,79 @@
+// Besides enum variants, #[sorted] checks the order of other lists that are
+// often kept alphabetized by hand:
+//
+//   - the fields of a struct,
+//   - the functions, constants and types of an impl block,
+//   - the named items of an inline module, such as a table of constants,
+//   - every {...} group of a use declaration, nested groups included.
+//
+// Items without a name of their own, like use declarations inside a module or
+// macro invocations inside an impl block, are not checked. Names are compared
+// as plain strings, so uppercase names sort before lowercase ones.
+
+use sorted::sorted;
+
+mod shapes {
+    pub mod area {
+        pub fn circle() {}
+        pub fn square() {}
+    }
+    pub mod perimeter {
+        pub fn circle() {}
+        pub fn square() {}
+    }
+    pub struct Circle;
+    pub struct Square;
+}
+
+#[sorted]
+use shapes::{Circle, Square, area::{circle, square}, perimeter};
+
+#[sorted]
+pub struct Config {
+    address: String,
+    port: u16,
+    timeout: u64,
+}
+
+#[sorted]
+impl Config {
+    const DEFAULT_PORT: u16 = 8080;
+
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn port(&self) -> u16 {
+        self.port
+    }
+
+    fn timeout(&self) -> u64 {
+        self.timeout
+    }
+}
+
+#[sorted]
+mod limits {
+    use std::time::Duration;
+
+    pub const MAX_CONNECTIONS: usize = 64;
+    pub const MAX_REQUEST: usize = 1 << 20;
+    pub const TIMEOUT: Duration = Duration::from_secs(30);
+}
+
+fn main() {
+    let config = Config {
+        address: "localhost".to_owned(),
+        port: Config::DEFAULT_PORT,
+        timeout: limits::TIMEOUT.as_secs(),
+    };
+    assert_eq!(config.address(), "localhost");
+    assert_eq!(config.port(), 8080);
+    assert_eq!(config.timeout(), 30);
+    assert!(limits::MAX_CONNECTIONS < limits::MAX_REQUEST);
+
+    circle();
+    square();
+    perimeter::circle();
+    let _ = (Circle, Square);
+}
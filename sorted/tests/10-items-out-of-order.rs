@@ -0,0 +1,39 @@
+// Out-of-order struct fields, impl items, module items and use groups are
+// reported with the same "X should sort before Y" diagnostics as enum
+// variants.
+
+use sorted::sorted;
+
+mod shapes {
+    pub fn circle() {}
+    pub fn square() {}
+    pub fn triangle() {}
+}
+
+#[sorted]
+use shapes::{circle, triangle, square};
+
+#[sorted]
+pub struct Config {
+    port: u16,
+    address: String,
+}
+
+#[sorted]
+impl Config {
+    fn port(&self) -> u16 {
+        self.port
+    }
+
+    fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+#[sorted]
+mod limits {
+    pub const TIMEOUT: u64 = 30;
+    pub const MAX_CONNECTIONS: usize = 64;
+}
+
+fn main() {}
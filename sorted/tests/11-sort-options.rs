@@ -0,0 +1,72 @@
+// By default names are compared as plain strings, so `Http10` sorts before
+// `Http2` and every uppercase name sorts before every lowercase one. Arguments
+// to #[sorted(...)] change the comparison:
+//
+//   - case_insensitive ignores the case of letters,
+//   - natural compares runs of digits as numbers, so `Http2` sorts before
+//     `Http10`,
+//   - reverse expects descending order,
+//   - by_discriminant, on enums only, expects ascending discriminants instead
+//     of names. Variants without an explicit discriminant follow the previous
+//     one, as they do in the compiled enum.
+//
+// Arguments can be combined, and are accepted on #[sorted] match expressions
+// as well. A wildcard arm stays last whatever the order.
+
+use sorted::sorted;
+
+#[sorted(natural)]
+pub enum Version {
+    Http1,
+    Http2,
+    Http10,
+    Http11,
+}
+
+#[sorted(case_insensitive)]
+#[allow(non_snake_case)]
+pub struct Flags {
+    alpha: bool,
+    Beta: bool,
+    gamma: bool,
+}
+
+#[sorted(case_insensitive, natural, reverse)]
+#[allow(non_camel_case_types)]
+pub enum Level {
+    Level10,
+    level9,
+    Level2,
+}
+
+#[sorted(by_discriminant)]
+#[repr(i8)]
+pub enum Code {
+    Negative = -1,
+    Zero,
+    Ok = 4,
+    Retry = 8,
+    Fail,
+}
+
+impl Version {
+    #[sorted::check]
+    fn name(&self) -> &'static str {
+        use self::Version::*;
+
+        #[sorted(natural)]
+        match self {
+            Http1 => "HTTP/1",
+            Http2 => "HTTP/2",
+            Http10 => "HTTP/1.0",
+            _ => "HTTP/1.1",
+        }
+    }
+}
+
+fn main() {
+    assert_eq!(Version::Http10.name(), "HTTP/1.0");
+    assert_eq!(Code::Zero as i8, 0);
+    assert_eq!(Code::Fail as i8, 9);
+    let _ = (Flags { alpha: true, Beta: true, gamma: true }, Level::level9);
+}
@@ -0,0 +1,35 @@
+// The arguments of #[sorted(...)] are checked too, and by_discriminant needs an
+// enum whose discriminants are integer literals.
+
+use sorted::sorted;
+
+#[sorted(natural)]
+pub enum Version {
+    Http1,
+    Http10,
+    Http2,
+}
+
+#[sorted(by_discriminant)]
+pub enum Code {
+    Ok = 4,
+    Retry = 2,
+}
+
+#[sorted(by_discriminant)]
+pub enum Computed {
+    One = 1,
+    Two = 1 + 1,
+}
+
+#[sorted(by_discriminant)]
+pub struct Flags {
+    alpha: bool,
+}
+
+#[sorted(alphabetical)]
+pub enum Unknown {
+    A,
+}
+
+fn main() {}
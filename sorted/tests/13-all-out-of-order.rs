@@ -0,0 +1,45 @@
+// Every misplaced name is reported, not just the first one. The names that
+// stay in place are the longest run already in order, and each other name is
+// reported against the name it should be moved next to, so that applying every
+// suggestion sorts the list.
+//
+// All #[sorted] match expressions of a function are reported as well.
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Error {
+    Zlib,
+    Config,
+    Database,
+    Auth,
+    Http,
+    Io,
+    Fmt,
+}
+
+#[sorted]
+use std::{io, fmt, collections::{HashSet, BTreeMap}};
+
+impl Error {
+    #[sorted::check]
+    fn describe(&self, other: &Error) {
+        use self::Error::*;
+
+        #[sorted]
+        match self {
+            Config => println!("config"),
+            Auth => println!("auth"),
+            _ => {}
+        }
+
+        #[sorted]
+        match other {
+            Io => println!("io"),
+            Http => println!("http"),
+            _ => {}
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,78 @@
+// A #[sorted] match does not have to be a statement of its own: it can be the
+// value of a let binding, the body of a closure, or sit in a nested block, an
+// if branch or another match. #[sorted::check] finds all of them.
+//
+// #[sorted::check] can also be put on an impl block or a module, checking the
+// match expressions of every function inside.
+
+use sorted::sorted;
+
+#[sorted]
+#[derive(Clone, Copy)]
+pub enum Shape {
+    Circle,
+    Square,
+    Triangle,
+}
+
+#[sorted::check]
+fn sides(shape: Shape, closed: bool) -> usize {
+    let count = #[sorted]
+    match shape {
+        Shape::Circle => 0,
+        Shape::Square => 4,
+        Shape::Triangle => 3,
+    };
+
+    let is_round = |shape: Shape| #[sorted] match shape {
+        Shape::Circle => true,
+        _ => false,
+    };
+
+    if closed {
+        {
+            #[sorted]
+            match shape {
+                Shape::Circle => usize::from(!is_round(shape)),
+                _ => count,
+            }
+        }
+    } else {
+        count
+    }
+}
+
+pub struct Canvas;
+
+#[sorted::check]
+impl Canvas {
+    fn name(&self, shape: Shape) -> &'static str {
+        #[sorted]
+        match shape {
+            Shape::Circle => "circle",
+            Shape::Square => "square",
+            Shape::Triangle => "triangle",
+        }
+    }
+}
+
+#[sorted::check]
+mod corners {
+    use super::Shape;
+
+    pub fn corners(shape: Shape) -> usize {
+        match shape {
+            Shape::Circle => 0,
+            other => #[sorted] match other {
+                Shape::Square => 4,
+                _ => 3,
+            },
+        }
+    }
+}
+
+fn main() {
+    assert_eq!(sides(Shape::Square, true), 4);
+    assert_eq!(Canvas.name(Shape::Triangle), "triangle");
+    assert_eq!(corners::corners(Shape::Triangle), 3);
+}
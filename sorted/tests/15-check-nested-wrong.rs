@@ -0,0 +1,40 @@
+// Out-of-order nested match expressions are reported like top-level ones.
+// #[sorted::check] only accepts functions, impl blocks and modules.
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Shape {
+    Circle,
+    Square,
+    Triangle,
+}
+
+pub struct Canvas;
+
+#[sorted::check]
+impl Canvas {
+    fn name(&self, shape: Shape) -> &'static str {
+        let name = #[sorted]
+        match shape {
+            Shape::Square => "square",
+            Shape::Circle => "circle",
+            Shape::Triangle => "triangle",
+        };
+        name
+    }
+
+    fn is_round(&self, shape: Shape) -> bool {
+        let round = |shape: Shape| #[sorted] match shape {
+            Shape::Triangle => false,
+            Shape::Circle => true,
+            _ => false,
+        };
+        round(shape)
+    }
+}
+
+#[sorted::check]
+pub struct Unchecked;
+
+fn main() {}
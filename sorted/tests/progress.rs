@@ -9,4 +9,11 @@ fn tests() {
     t.compile_fail("tests/06-pattern-path.rs");
     t.compile_fail("tests/07-unrecognized-pattern.rs");
     t.pass("tests/08-underscore.rs");
+    t.pass("tests/09-sorted-items.rs");
+    t.compile_fail("tests/10-items-out-of-order.rs");
+    t.pass("tests/11-sort-options.rs");
+    t.compile_fail("tests/12-sort-options-wrong.rs");
+    t.compile_fail("tests/13-all-out-of-order.rs");
+    t.pass("tests/14-check-nested.rs");
+    t.compile_fail("tests/15-check-nested-wrong.rs");
 }
\ No newline at end of file